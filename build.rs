@@ -0,0 +1,10 @@
+fn main() {
+    println!("cargo:rerun-if-changed=proto");
+    #[cfg(feature = "grpc")]
+    {
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc is available");
+        std::env::set_var("PROTOC", protoc);
+        tonic_prost_build::compile_protos("proto/grid_calculator.proto")
+            .expect("proto/grid_calculator.proto compiles");
+    }
+}
@@ -0,0 +1,678 @@
+use crate::error::{Error, Result};
+use crate::watch::WatchUpdate;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// The `[alerts]` section: what to alert on while watching prices, and
+/// where alerts are sent
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AlertConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<AlertRule>,
+    /// Where alerts go; stdout if none are configured
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sinks: Vec<SinkConfig>,
+}
+
+/// A condition to alert on
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum AlertRule {
+    /// The price comes within `percent` of the next unfilled level
+    Approach { percent: f64 },
+    /// The market reaches an unfilled level, filling its order
+    Fill {},
+    /// The position, counting crossed levels as filled, exceeds a limit
+    Exposure {
+        /// Largest position size
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_position: Option<f64>,
+        /// Largest cost of the position, in the quote currency
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_cost: Option<f64>,
+    },
+}
+
+/// Where alerts are sent
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum SinkConfig {
+    /// Print each alert
+    Stdout {},
+    /// Append each alert as a line to a file
+    Log { path: PathBuf },
+    /// Run a program for each alert, with the alert as JSON on its stdin
+    /// and in `GRID_ALERT_*` environment variables
+    Command {
+        program: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+    },
+    /// POST each alert as JSON to a URL
+    Webhook { url: String },
+}
+
+impl AlertConfig {
+    /// Every invalid value in the section
+    pub fn errors(&self) -> Vec<Error> {
+        let mut errors = Vec::new();
+        for (i, rule) in self.rules.iter().enumerate() {
+            let field = |name: &str| format!("alerts.rules[{}].{}", i, name);
+            match rule {
+                AlertRule::Approach { percent } => {
+                    if !(*percent > 0.0 && *percent < 100.0) {
+                        errors.push(Error::invalid_field(
+                            field("percent"),
+                            format!(
+                                "Approach percent must be between 0 and 100, got {}",
+                                percent
+                            ),
+                        ));
+                    }
+                }
+                AlertRule::Fill {} => {}
+                AlertRule::Exposure {
+                    max_position,
+                    max_cost,
+                } => {
+                    if max_position.is_none() && max_cost.is_none() {
+                        errors.push(Error::constraint(
+                            field("max_position"),
+                            "Exposure rule needs max_position or max_cost",
+                        ));
+                    }
+                    for (name, limit) in [("max_position", max_position), ("max_cost", max_cost)] {
+                        if let Some(limit) = limit.filter(|l| *l <= 0.0 || !l.is_finite()) {
+                            errors.push(Error::invalid_field(
+                                field(name),
+                                format!("{} must be positive, got {}", name, limit),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        for (i, sink) in self.sinks.iter().enumerate() {
+            let field = |name: &str| format!("alerts.sinks[{}].{}", i, name);
+            match sink {
+                SinkConfig::Stdout {} => {}
+                SinkConfig::Log { path } if path.as_os_str().is_empty() => {
+                    errors.push(Error::invalid_field(field("path"), "Log path is empty"));
+                }
+                SinkConfig::Log { .. } => {}
+                SinkConfig::Command { program, .. } if program.trim().is_empty() => {
+                    errors.push(Error::invalid_field(field("program"), "Program is empty"));
+                }
+                SinkConfig::Command { .. } => {}
+                SinkConfig::Webhook { url } => {
+                    if !(url.starts_with("http://") || url.starts_with("https://")) {
+                        errors.push(Error::invalid_field(
+                            field("url"),
+                            format!(
+                                "Webhook URL must start with http:// or https://, got '{}'",
+                                url
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// What an alert is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertKind {
+    Approach,
+    Fill,
+    Exposure,
+}
+
+impl AlertKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertKind::Approach => "approach",
+            AlertKind::Fill => "fill",
+            AlertKind::Exposure => "exposure",
+        }
+    }
+}
+
+/// A rule that triggered
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub timestamp: DateTime<Utc>,
+    /// Market price that triggered the alert
+    pub price: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<usize>,
+    pub message: String,
+}
+
+/// Somewhere alerts can be sent
+pub trait Sink {
+    /// Name of the sink in error messages
+    fn name(&self) -> String;
+
+    fn send(&mut self, alert: &Alert) -> Result<()>;
+}
+
+pub struct StdoutSink;
+
+impl Sink for StdoutSink {
+    fn name(&self) -> String {
+        "stdout".to_string()
+    }
+
+    fn send(&mut self, alert: &Alert) -> Result<()> {
+        println!("ALERT [{}] {}", alert.kind.as_str(), alert.message);
+        Ok(())
+    }
+}
+
+pub struct LogSink {
+    pub path: PathBuf,
+}
+
+impl Sink for LogSink {
+    fn name(&self) -> String {
+        format!("log {}", self.path.display())
+    }
+
+    fn send(&mut self, alert: &Alert) -> Result<()> {
+        let io_error = |source| Error::Io {
+            path: self.path.clone(),
+            source,
+        };
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(io_error)?;
+        writeln!(
+            file,
+            "{} [{}] {}",
+            alert.timestamp.to_rfc3339(),
+            alert.kind.as_str(),
+            alert.message
+        )
+        .map_err(io_error)
+    }
+}
+
+pub struct CommandSink {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl Sink for CommandSink {
+    fn name(&self) -> String {
+        format!("command {}", self.program)
+    }
+
+    fn send(&mut self, alert: &Alert) -> Result<()> {
+        let failed = |message: String| Error::Alert {
+            sink: self.name(),
+            message,
+        };
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .env("GRID_ALERT_KIND", alert.kind.as_str())
+            .env("GRID_ALERT_PRICE", alert.price.to_string())
+            .env(
+                "GRID_ALERT_LEVEL",
+                alert.level.map(|l| l.to_string()).unwrap_or_default(),
+            )
+            .env("GRID_ALERT_MESSAGE", &alert.message)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| failed(e.to_string()))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // A program that ignores its input may exit before reading it
+            let _ = writeln!(stdin, "{}", to_json(alert));
+        }
+        let status = child.wait().map_err(|e| failed(e.to_string()))?;
+        if !status.success() {
+            return Err(failed(format!("exited with {}", status)));
+        }
+        Ok(())
+    }
+}
+
+pub struct WebhookSink {
+    pub url: String,
+    agent: ureq::Agent,
+}
+
+impl WebhookSink {
+    /// How long a webhook may take to answer before the alert is dropped
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            agent: ureq::AgentBuilder::new().timeout(Self::TIMEOUT).build(),
+        }
+    }
+}
+
+impl Sink for WebhookSink {
+    fn name(&self) -> String {
+        format!("webhook {}", self.url)
+    }
+
+    fn send(&mut self, alert: &Alert) -> Result<()> {
+        self.agent
+            .post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&to_json(alert))
+            .map(|_| ())
+            .map_err(|e| Error::Alert {
+                sink: self.name(),
+                message: e.to_string(),
+            })
+    }
+}
+
+/// Create the sink a config entry describes
+pub fn sink(config: &SinkConfig) -> Box<dyn Sink> {
+    match config {
+        SinkConfig::Stdout {} => Box::new(StdoutSink),
+        SinkConfig::Log { path } => Box::new(LogSink { path: path.clone() }),
+        SinkConfig::Command { program, args } => Box::new(CommandSink {
+            program: program.clone(),
+            args: args.clone(),
+        }),
+        SinkConfig::Webhook { url } => Box::new(WebhookSink::new(url)),
+    }
+}
+
+fn to_json(alert: &Alert) -> String {
+    serde_json::to_string(alert).expect("alerts serialize")
+}
+
+/// Checks watch updates against the alert rules and sends what triggers.
+/// Each level is alerted on once per rule, and each exposure rule once.
+pub struct Alerter {
+    rules: Vec<AlertRule>,
+    sinks: Vec<Box<dyn Sink>>,
+    /// Rule index and level of every approach and fill already alerted on
+    alerted: HashSet<(usize, usize)>,
+    /// Exposure rules that have triggered
+    exposed: HashSet<usize>,
+}
+
+impl Alerter {
+    /// Alert on the config's rules, sending to its sinks
+    pub fn new(config: &AlertConfig) -> Self {
+        let sinks = if config.sinks.is_empty() {
+            vec![Box::new(StdoutSink) as Box<dyn Sink>]
+        } else {
+            config.sinks.iter().map(sink).collect()
+        };
+        Self::with_sinks(config.rules.clone(), sinks)
+    }
+
+    /// Alert on `rules`, sending to any sinks
+    pub fn with_sinks(rules: Vec<AlertRule>, sinks: Vec<Box<dyn Sink>>) -> Self {
+        Self {
+            rules,
+            sinks,
+            alerted: HashSet::new(),
+            exposed: HashSet::new(),
+        }
+    }
+
+    /// Alerts newly triggered by an update
+    pub fn check(&mut self, update: &WatchUpdate) -> Vec<Alert> {
+        let alert = |kind, level, message| Alert {
+            kind,
+            timestamp: update.timestamp,
+            price: update.price,
+            level,
+            message,
+        };
+        let mut alerts = Vec::new();
+
+        for (i, rule) in self.rules.iter().enumerate() {
+            match rule {
+                AlertRule::Approach { percent } => {
+                    let Some(next) = &update.next else { continue };
+                    if next.distance_percent <= *percent && self.alerted.insert((i, next.level)) {
+                        alerts.push(alert(
+                            AlertKind::Approach,
+                            Some(next.level),
+                            format!(
+                                "Price {:.2} is {:.2}% above level {} at {:.2}",
+                                update.price, next.distance_percent, next.level, next.price
+                            ),
+                        ));
+                    }
+                }
+                AlertRule::Fill {} => {
+                    for level in &update.crossed {
+                        if update.newly_crossed.contains(&level.level)
+                            && self.alerted.insert((i, level.level))
+                        {
+                            alerts.push(alert(
+                                AlertKind::Fill,
+                                Some(level.level),
+                                format!(
+                                    "Level {} at {:.2} reached by price {:.2}",
+                                    level.level, level.result.grid_price, update.price
+                                ),
+                            ));
+                        }
+                    }
+                }
+                AlertRule::Exposure {
+                    max_position,
+                    max_cost,
+                } => {
+                    // Crossed levels fill in ladder order, so the last one
+                    // carries the whole position
+                    let (position, cost) = update
+                        .crossed
+                        .last()
+                        .map_or((update.pnl.position, update.pnl.cost), |l| {
+                            (l.result.total_position, l.result.total_cost)
+                        });
+                    let exceeded: Vec<String> = [
+                        ("max_position", *max_position, position),
+                        ("max_cost", *max_cost, cost),
+                    ]
+                    .into_iter()
+                    .filter_map(|(name, limit, value)| {
+                        limit
+                            .filter(|l| value > *l)
+                            .map(|l| format!("{} {}", name, l))
+                    })
+                    .collect();
+                    if !exceeded.is_empty() && self.exposed.insert(i) {
+                        alerts.push(alert(
+                            AlertKind::Exposure,
+                            None,
+                            format!(
+                                "Position {:.4} costing {:.2} exceeds {}",
+                                position,
+                                cost,
+                                exceeded.join(" and ")
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        alerts
+    }
+
+    /// Send an alert to every sink. A failing sink does not stop the
+    /// others; its error is returned.
+    pub fn send(&mut self, alert: &Alert) -> Vec<Error> {
+        self.sinks
+            .iter_mut()
+            .filter_map(|sink| sink.send(alert).err())
+            .collect()
+    }
+
+    /// Check an update and send what triggers, returning sink errors
+    pub fn process(&mut self, update: &WatchUpdate) -> Vec<Error> {
+        let alerts = self.check(update);
+        alerts.iter().flat_map(|alert| self.send(alert)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::diagnostics::Severity;
+    use crate::feed::PriceTick;
+    use crate::state::GridState;
+    use crate::watch::Watcher;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    const CONFIG: &str = r#"
+[base]
+initial_price = 100.0
+grid_type = "fixed"
+
+[grid]
+levels = [1.0, 2.0, 4.0]
+
+[position]
+mode = "fixed"
+base_size = 10.0
+
+[[alerts.rules]]
+type = "approach"
+percent = 0.5
+
+[[alerts.rules]]
+type = "fill"
+
+[[alerts.rules]]
+type = "exposure"
+max_position = 25.0
+
+[[alerts.sinks]]
+type = "webhook"
+url = "http://127.0.0.1:9/hook"
+"#;
+
+    /// A sink that keeps what it is sent
+    struct Recorder(Arc<Mutex<Vec<Alert>>>);
+
+    impl Sink for Recorder {
+        fn name(&self) -> String {
+            "recorder".to_string()
+        }
+
+        fn send(&mut self, alert: &Alert) -> Result<()> {
+            self.0.lock().unwrap().push(alert.clone());
+            Ok(())
+        }
+    }
+
+    fn update(watcher: &mut Watcher, price: f64) -> WatchUpdate {
+        watcher.update(&PriceTick {
+            timestamp: "2024-05-01T00:00:00Z".parse().unwrap(),
+            price,
+        })
+    }
+
+    fn alert() -> Alert {
+        Alert {
+            kind: AlertKind::Fill,
+            timestamp: "2024-05-01T00:00:00Z".parse().unwrap(),
+            price: 98.5,
+            level: Some(1),
+            message: "Level 1 at 99.00 reached by price 98.50".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rules() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let alerts = config.alerts.clone().unwrap();
+        assert!(alerts.errors().is_empty());
+
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let mut alerter = Alerter::with_sinks(alerts.rules, vec![Box::new(Recorder(sent.clone()))]);
+        let mut watcher = Watcher::new(GridState::new(config, None).unwrap());
+
+        assert!(alerter.process(&update(&mut watcher, 100.0)).is_empty());
+        assert!(sent.lock().unwrap().is_empty());
+
+        alerter.process(&update(&mut watcher, 99.3));
+        alerter.process(&update(&mut watcher, 99.4));
+        let kinds = |sent: &[Alert]| -> Vec<(AlertKind, Option<usize>)> {
+            sent.iter().map(|a| (a.kind, a.level)).collect()
+        };
+        assert_eq!(
+            kinds(&sent.lock().unwrap()),
+            [(AlertKind::Approach, Some(1))]
+        );
+
+        // Crossing two levels fills them; the second takes the position to 20
+        sent.lock().unwrap().clear();
+        alerter.process(&update(&mut watcher, 97.9));
+        assert_eq!(
+            kinds(&sent.lock().unwrap()),
+            [(AlertKind::Fill, Some(1)), (AlertKind::Fill, Some(2))]
+        );
+
+        // Level 3 takes it to 30, past the limit of 25
+        sent.lock().unwrap().clear();
+        alerter.process(&update(&mut watcher, 96.4));
+        alerter.process(&update(&mut watcher, 95.0));
+        assert_eq!(
+            kinds(&sent.lock().unwrap()),
+            [
+                (AlertKind::Approach, Some(3)),
+                (AlertKind::Fill, Some(3)),
+                (AlertKind::Exposure, None)
+            ]
+        );
+        assert_eq!(
+            sent.lock().unwrap()[2].message,
+            "Position 30.0000 costing 2930.00 exceeds max_position 25"
+        );
+    }
+
+    #[test]
+    fn test_invalid_section() {
+        let config: Config = toml::from_str(
+            &CONFIG
+                .replace("percent = 0.5", "percent = 0.0")
+                .replace("max_position = 25.0", "")
+                .replace("http://127.0.0.1:9/hook", "ftp://host"),
+        )
+        .unwrap();
+        let fields: Vec<String> = config
+            .errors()
+            .iter()
+            .filter_map(|e| e.field().map(String::from))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "alerts.rules[0].percent",
+                "alerts.rules[2].max_position",
+                "alerts.sinks[0].url"
+            ]
+        );
+
+        let unknown = CONFIG.replace("type = \"fill\"", "type = \"fill\"\npercent = 1.0");
+        assert!(toml::from_str::<Config>(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_diagnostics_with_strategies() {
+        let source = format!(
+            "{}\n[[strategies]]\nname = \"a\"\nextends = \"main\"\n\n\
+             [[strategies]]\nname = \"b\"\nextends = \"main\"\n",
+            CONFIG.replace("percent = 0.5", "percent = 0.0")
+        );
+        let config: Config = toml::from_str(&source).unwrap();
+        let errors: Vec<Option<String>> = config
+            .diagnostics()
+            .into_iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| d.path)
+            .collect();
+        // Strategies do not repeat the main config's alert errors
+        assert_eq!(errors, [Some("alerts.rules[0].percent".to_string())]);
+    }
+
+    #[test]
+    fn test_log_sink() {
+        let path = std::env::temp_dir().join(format!("grid-alerts-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut sink = LogSink { path: path.clone() };
+        sink.send(&alert()).unwrap();
+        sink.send(&alert()).unwrap();
+        let log = fs::read_to_string(&path).unwrap();
+        assert_eq!(log.lines().count(), 2);
+        assert_eq!(
+            log.lines().next().unwrap(),
+            "2024-05-01T00:00:00+00:00 [fill] Level 1 at 99.00 reached by price 98.50"
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_sink() {
+        let path = std::env::temp_dir().join(format!("grid-alert-cmd-{}.txt", std::process::id()));
+        let mut sink = CommandSink {
+            program: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                format!(
+                    "{{ echo \"$GRID_ALERT_KIND $GRID_ALERT_LEVEL\"; cat; }} > {}",
+                    path.display()
+                ),
+            ],
+        };
+        sink.send(&alert()).unwrap();
+        let output = fs::read_to_string(&path).unwrap();
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some("fill 1"));
+        let json: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+        assert_eq!(json["price"], 98.5);
+        fs::remove_file(path).unwrap();
+
+        let mut failing = CommandSink {
+            program: "sh".to_string(),
+            args: vec!["-c".to_string(), "exit 3".to_string()],
+        };
+        let err = failing.send(&alert()).unwrap_err();
+        assert!(err.to_string().contains("command sh"), "{}", err);
+    }
+
+    #[test]
+    fn test_webhook_sink() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", server.server_addr().to_ip().unwrap());
+        let stub = thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let received = (
+                request.method().to_string(),
+                request.url().to_string(),
+                body,
+            );
+            request.respond(tiny_http::Response::empty(204)).unwrap();
+
+            // A failing endpoint is reported as an error
+            let request = server.recv().unwrap();
+            request.respond(tiny_http::Response::empty(500)).unwrap();
+            received
+        });
+
+        let mut sink = WebhookSink::new(url);
+        sink.send(&alert()).unwrap();
+        assert!(sink.send(&alert()).is_err());
+
+        let (method, path, body) = stub.join().unwrap();
+        assert_eq!((method.as_str(), path.as_str()), ("POST", "/hook"));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["kind"], "fill");
+        assert_eq!(json["level"], 1);
+    }
+}
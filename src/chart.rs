@@ -0,0 +1,174 @@
+use crate::grid_calculator::GridResult;
+
+/// Number of rows used for the plot area
+pub const CHART_HEIGHT: usize = 16;
+
+/// Narrowest chart that still leaves room for axis labels
+pub const MIN_WIDTH: usize = 30;
+
+const LABEL_WIDTH: usize = 10;
+const GRID_MARK: char = '●';
+const AVERAGE_MARK: char = '·';
+
+/// Render grid prices and the moving average price against cumulative
+/// position as a Unicode chart that fits in `width` columns.
+pub fn render(history: &[GridResult], width: usize) -> String {
+    if history.is_empty() {
+        return String::new();
+    }
+
+    let plot_width = width.max(MIN_WIDTH) - LABEL_WIDTH - 1;
+    let height = CHART_HEIGHT;
+
+    let x_max = history
+        .iter()
+        .map(|r| r.total_position)
+        .fold(0.0_f64, f64::max);
+    let (mut y_min, mut y_max) = history.iter().fold((f64::MAX, f64::MIN), |(lo, hi), r| {
+        (
+            lo.min(r.grid_price).min(r.average_price),
+            hi.max(r.grid_price).max(r.average_price),
+        )
+    });
+    if (y_max - y_min).abs() < f64::EPSILON {
+        // Flat series, pad so the points land mid-chart
+        y_min -= 1.0;
+        y_max += 1.0;
+    }
+
+    let to_col = |x: f64| -> usize {
+        if x_max <= 0.0 {
+            0
+        } else {
+            ((x / x_max) * (plot_width - 1) as f64).round() as usize
+        }
+    };
+    let to_row = |y: f64| -> usize {
+        (((y_max - y) / (y_max - y_min)) * (height - 1) as f64).round() as usize
+    };
+
+    let mut canvas = vec![vec![' '; plot_width]; height];
+
+    // Average price line, interpolated between consecutive grid fills
+    let mut prev: Option<(usize, f64)> = None;
+    for result in history {
+        let col = to_col(result.total_position);
+        if let Some((prev_col, prev_avg)) = prev {
+            // Histories from a calculator with a negative multiplier shrink the
+            // position, so the line may run right to left
+            let span = col.abs_diff(prev_col);
+            for step in 0..=span {
+                let t = if span == 0 {
+                    1.0
+                } else {
+                    step as f64 / span as f64
+                };
+                let avg = prev_avg + (result.average_price - prev_avg) * t;
+                let x = if col >= prev_col {
+                    prev_col + step
+                } else {
+                    prev_col - step
+                };
+                canvas[to_row(avg)][x] = AVERAGE_MARK;
+            }
+        } else {
+            canvas[to_row(result.average_price)][col] = AVERAGE_MARK;
+        }
+        prev = Some((col, result.average_price));
+    }
+
+    // Grid price points drawn last so they stay visible over the average line
+    for result in history {
+        canvas[to_row(result.grid_price)][to_col(result.total_position)] = GRID_MARK;
+    }
+
+    let mut output = String::new();
+    output.push_str(&format!(
+        "{:>w$} {} Grid Price   {} Avg Price\n",
+        "",
+        GRID_MARK,
+        AVERAGE_MARK,
+        w = LABEL_WIDTH
+    ));
+
+    for (i, row) in canvas.iter().enumerate() {
+        let label = if i == 0 {
+            format!("{:.2}", y_max)
+        } else if i == height - 1 {
+            format!("{:.2}", y_min)
+        } else if i == (height - 1) / 2 {
+            format!("{:.2}", (y_max + y_min) / 2.0)
+        } else {
+            String::new()
+        };
+        let line: String = row.iter().collect();
+        output.push_str(&format!(
+            "{:>w$}│{}\n",
+            label,
+            line.trim_end(),
+            w = LABEL_WIDTH
+        ));
+    }
+
+    output.push_str(&format!(
+        "{:>w$}└{}\n",
+        "",
+        "─".repeat(plot_width),
+        w = LABEL_WIDTH
+    ));
+
+    let max_label = format!("{:.2}", x_max);
+    let gap = plot_width.saturating_sub(1 + max_label.len());
+    output.push_str(&format!(
+        "{:>w$} 0{}{}\n",
+        "",
+        " ".repeat(gap),
+        max_label,
+        w = LABEL_WIDTH
+    ));
+    output.push_str(&format!(
+        "{:>w$} {:^pw$}\n",
+        "",
+        "Total Position",
+        w = LABEL_WIDTH,
+        pw = plot_width
+    ));
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid_calculator::{GridCalculator, GridType, PositionMode};
+
+    fn sample_history() -> Vec<GridResult> {
+        let mut calc = GridCalculator::new(100.0, GridType::Fixed, PositionMode::Fixed, 100.0, 1.0);
+        for level in [1.0, 2.0, 3.0, 5.0] {
+            calc.calculate_grid(level);
+        }
+        calc.get_history().to_vec()
+    }
+
+    #[test]
+    fn test_render_fits_width() {
+        let chart = render(&sample_history(), 60);
+        for line in chart.lines() {
+            assert!(line.chars().count() <= 60, "line too wide: {:?}", line);
+        }
+        assert_eq!(chart.matches(GRID_MARK).count(), 4 + 1); // points + legend
+    }
+
+    #[test]
+    fn test_render_axis_labels() {
+        let chart = render(&sample_history(), 80);
+        assert!(chart.contains("99.00"));
+        assert!(chart.contains("95.00"));
+        assert!(chart.contains("400.00"));
+    }
+
+    #[test]
+    fn test_render_empty_history() {
+        assert!(render(&[], 80).is_empty());
+    }
+}
@@ -1,137 +1,388 @@
+pub mod format;
+pub mod include;
+pub mod lint;
+pub mod migrate;
+pub mod overrides;
+pub mod resolve;
+pub mod schema;
+
+use crate::alert::AlertConfig;
+use crate::diagnostics::Diagnostic;
+use crate::error::{Error, Result};
+use crate::grid_calculator::{GridCalculator, GridType, PositionMode};
+use crate::portfolio::PortfolioConfig;
+use format::Format;
+use lint::{Lint, LintLevel};
+use resolve::{ResolvedStrategy, Source, MAIN_CONFIG};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::collections::BTreeMap;
 use std::path::Path;
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// A grid trading configuration: the main grid plus optional named strategies
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct Config {
+    /// Version of the config shape; older files are upgraded when loaded
+    #[serde(default = "migrate::current_version")]
+    pub version: u32,
+    /// Strategy library files to merge in, as paths or glob patterns
+    /// relative to this file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include: Option<Vec<String>>,
     pub base: BaseConfig,
     pub grid: GridConfig,
     pub position: PositionConfig,
+    /// Named variations of the main configuration
     #[serde(skip_serializing_if = "Option::is_none")]
     pub strategies: Option<Vec<Strategy>>,
+    /// Level for each lint, overriding its default of `warn`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "schema::lints")]
+    pub lints: Option<BTreeMap<Lint, LintLevel>>,
+    /// Alerts raised while watching a price feed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alerts: Option<AlertConfig>,
+    /// Grids on several symbols sharing one pool of capital
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub portfolio: Option<PortfolioConfig>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Starting price and how grid prices are placed
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct BaseConfig {
+    /// Price the grid starts from
     pub initial_price: f64,
-    pub grid_type: String,
+    pub grid_type: GridType,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Grid levels, applied in order
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct GridConfig {
+    /// Drop of each grid level in percent, between 0 and 100 (exclusive)
+    #[schemars(schema_with = "schema::levels")]
     pub levels: Vec<f64>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Position sizing at each grid level
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct PositionConfig {
-    pub mode: String,
+    pub mode: PositionMode,
+    /// Position size at the first grid level
     pub base_size: f64,
+    /// Scale factor for the multiple modes; required unless `mode` is `fixed`
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "schema::multiplier")]
     pub multiplier: Option<f64>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// A named variation of the config, with the same `base`, `grid` and
+/// `position` sections. Fields left out are inherited through `extends`,
+/// which names another strategy or `"main"` for the main config.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Strategy {
+    /// Unique name of the strategy; `main` is reserved
     pub name: String,
-    pub initial_price: f64,
-    pub grid_type: String,
-    pub levels: Vec<f64>,
-    pub position_mode: String,
-    pub base_size: f64,
+    /// Strategy to inherit unset fields from, or `main` for the main config
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    #[serde(default, skip_serializing_if = "PartialBase::is_empty")]
+    pub base: PartialBase,
+    #[serde(default, skip_serializing_if = "PartialGrid::is_empty")]
+    pub grid: PartialGrid,
+    #[serde(default, skip_serializing_if = "PartialPosition::is_empty")]
+    pub position: PartialPosition,
+}
+
+/// The fields of `BaseConfig` a strategy sets itself
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PartialBase {
+    /// Price the grid starts from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grid_type: Option<GridType>,
+}
+
+/// The fields of `GridConfig` a strategy sets itself
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PartialGrid {
+    /// Drop of each grid level in percent, between 0 and 100 (exclusive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "schema::levels")]
+    pub levels: Option<Vec<f64>>,
+}
+
+/// The fields of `PositionConfig` a strategy sets itself
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PartialPosition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<PositionMode>,
+    /// Position size at the first grid level
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_size: Option<f64>,
+    /// Scale factor for the multiple modes; required unless `mode` is `fixed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "schema::multiplier")]
     pub multiplier: Option<f64>,
 }
 
+impl PartialBase {
+    pub fn is_empty(&self) -> bool {
+        *self == PartialBase::default()
+    }
+}
+
+impl PartialGrid {
+    pub fn is_empty(&self) -> bool {
+        *self == PartialGrid::default()
+    }
+}
+
+impl PartialPosition {
+    pub fn is_empty(&self) -> bool {
+        *self == PartialPosition::default()
+    }
+}
+
 impl Config {
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
-        let contents = fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&contents)?;
-        Ok(config)
+    /// Read a config, choosing TOML, JSON or YAML by the file's extension
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        Config::from_file_as(path, Format::detect(path))
     }
 
-    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
-        let contents = toml::to_string_pretty(self)?;
-        fs::write(path, contents)?;
-        Ok(())
+    /// Write a config, choosing TOML, JSON or YAML by the file's extension
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.to_file_as(path, Format::detect(path))
     }
 
-    pub fn validate(&self) -> Result<(), String> {
-        // Validate grid_type
-        if !["fixed", "average"].contains(&self.base.grid_type.as_str()) {
-            return Err(format!("Invalid grid_type: {}. Must be 'fixed' or 'average'", self.base.grid_type));
-        }
+    pub fn validate(&self) -> Result<()> {
+        first_error(self.errors())
+    }
 
-        // Validate position mode
-        if !["fixed", "current-multiple", "increment-multiple"].contains(&self.position.mode.as_str()) {
-            return Err(format!("Invalid position mode: {}. Must be 'fixed', 'current-multiple', or 'increment-multiple'", self.position.mode));
+    /// Every validation error in the main configuration
+    pub fn errors(&self) -> Vec<Error> {
+        let mut errors = Vec::new();
+        if self.version > migrate::CURRENT_VERSION {
+            errors.push(migrate::newer_version(self.version));
         }
-
-        // Validate multiplier is present when needed
-        if self.position.mode.contains("multiple") && self.position.multiplier.is_none() {
-            return Err("Multiplier is required for multiple position modes".to_string());
+        errors.extend(multiplier_errors(
+            self.position.mode,
+            self.position.multiplier,
+            "position.multiplier",
+        ));
+        errors.extend(level_errors(&self.grid.levels, "grid.levels"));
+        if let Some(alerts) = &self.alerts {
+            errors.extend(alerts.errors());
         }
-
-        // Validate levels
-        if self.grid.levels.is_empty() {
-            return Err("Grid levels cannot be empty".to_string());
+        if let Some(portfolio) = &self.portfolio {
+            errors.extend(portfolio.errors(self));
         }
+        errors
+    }
 
-        for level in &self.grid.levels {
-            if *level <= 0.0 || *level >= 100.0 {
-                return Err(format!("Invalid grid level: {}. Must be between 0 and 100", level));
+    /// Validate the main configuration and every strategy, collecting all
+    /// problems instead of stopping at the first one. Lint findings are
+    /// included at their configured level.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = self
+            .errors()
+            .iter()
+            .map(|e| Diagnostic::from_error(e, None))
+            .collect();
+
+        let strategies = self.strategies.as_deref().unwrap_or_default();
+        for (i, strategy) in strategies.iter().enumerate() {
+            let prefix = format!("strategies[{}]", i);
+            match strategy.resolve(self) {
+                Ok(resolved) => {
+                    for error in resolved.config.errors() {
+                        let Some(field) = error.field() else {
+                            continue;
+                        };
+                        // Inherited values are reported where they are defined,
+                        // once by the layer itself if it is invalid there too
+                        let path = match resolved.own_path(i, field) {
+                            Some(path) => path,
+                            None if self.reported_by_source(&resolved, field) => continue,
+                            None => match resolved.defined_at(self, field) {
+                                Some(path) => path,
+                                None => continue,
+                            },
+                        };
+                        let message = error.in_strategy(&strategy.name).to_string();
+                        diagnostics.push(Diagnostic::error(Some(path), message));
+                    }
+                }
+                Err(error) => diagnostics.push(Diagnostic::from_error(&error, Some(&prefix))),
+            }
+            if strategy.name == MAIN_CONFIG {
+                let error = Error::invalid_field(
+                    "name",
+                    format!(
+                        "Strategy name '{}' is reserved for the main config",
+                        MAIN_CONFIG
+                    ),
+                )
+                .in_strategy(&strategy.name);
+                diagnostics.push(Diagnostic::from_error(&error, Some(&prefix)));
+            }
+            if strategies[..i].iter().any(|s| s.name == strategy.name) {
+                let error = Error::invalid_field(
+                    "name",
+                    format!("Duplicate strategy name '{}'", strategy.name),
+                )
+                .in_strategy(&strategy.name);
+                diagnostics.push(Diagnostic::from_error(&error, Some(&prefix)));
             }
         }
 
-        Ok(())
+        diagnostics.extend(lint::lint(self));
+        diagnostics
+    }
+
+    /// Whether the layer a strategy inherits `field` from is itself invalid
+    /// at that field, so its own diagnostics already cover the value
+    fn reported_by_source(&self, resolved: &ResolvedStrategy, field: &str) -> bool {
+        let errors = match resolved.source_of(field) {
+            Some(Source::Main) => self.errors(),
+            Some(Source::Strategy(name)) => match self.resolve_strategy(name) {
+                Ok(source) => source.config.errors(),
+                Err(_) => return true,
+            },
+            None => return false,
+        };
+        errors.iter().any(|e| e.field() == Some(field))
+    }
+
+    /// Level a lint is reported at, from `[lints]` or the lint's default
+    pub fn lint_level(&self, lint: Lint) -> LintLevel {
+        self.lints
+            .as_ref()
+            .and_then(|lints| lints.get(&lint).copied())
+            .unwrap_or_else(|| lint.default_level())
+    }
+
+    /// Look up a named strategy
+    pub fn strategy(&self, name: &str) -> Result<&Strategy> {
+        self.strategies
+            .iter()
+            .flatten()
+            .find(|s| s.name == name)
+            .ok_or_else(|| Error::StrategyNotFound {
+                name: name.to_string(),
+            })
+    }
+
+    /// Look up a named strategy and merge it with everything it extends
+    pub fn resolve_strategy(&self, name: &str) -> Result<ResolvedStrategy> {
+        self.strategy(name)?.resolve(self)
+    }
+
+    /// Create a calculator for the main configuration
+    pub fn calculator(&self) -> GridCalculator {
+        GridCalculator::new(
+            self.base.initial_price,
+            self.base.grid_type,
+            self.position.mode,
+            self.position.base_size,
+            self.position.multiplier.unwrap_or(1.0),
+        )
     }
 }
 
-impl Strategy {
-    pub fn validate(&self) -> Result<(), String> {
-        // Validate grid_type
-        if !["fixed", "average"].contains(&self.grid_type.as_str()) {
-            return Err(format!("Invalid grid_type in strategy '{}': {}. Must be 'fixed' or 'average'", self.name, self.grid_type));
-        }
+fn first_error(errors: Vec<Error>) -> Result<()> {
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
 
-        // Validate position mode
-        if !["fixed", "current-multiple", "increment-multiple"].contains(&self.position_mode.as_str()) {
-            return Err(format!("Invalid position mode in strategy '{}': {}. Must be 'fixed', 'current-multiple', or 'increment-multiple'", self.name, self.position_mode));
-        }
+/// Report a missing or non-positive multiplier when the position mode
+/// needs one. `field` is the path of the multiplier, used in errors.
+pub fn multiplier_errors(mode: PositionMode, multiplier: Option<f64>, field: &str) -> Vec<Error> {
+    if !mode.uses_multiplier() {
+        return Vec::new();
+    }
+    match multiplier {
+        None => vec![Error::constraint(
+            field,
+            format!("Multiplier is required for position mode '{}'", mode),
+        )],
+        Some(m) if m <= 0.0 || !m.is_finite() => vec![Error::invalid_field(
+            field,
+            format!("Multiplier must be positive, got {}", m),
+        )],
+        Some(_) => Vec::new(),
+    }
+}
 
-        // Validate multiplier is present when needed
-        if self.position_mode.contains("multiple") && self.multiplier.is_none() {
-            return Err(format!("Multiplier is required for strategy '{}' with position mode '{}'", self.name, self.position_mode));
-        }
+/// Report grid levels that are missing or not percentages between 0 and
+/// 100. `field` is the path of the levels list, used in errors.
+pub fn level_errors(levels: &[f64], field: &str) -> Vec<Error> {
+    if levels.is_empty() {
+        return vec![Error::invalid_field(field, "Grid levels cannot be empty")];
+    }
 
-        // Validate levels
-        if self.levels.is_empty() {
-            return Err(format!("Grid levels cannot be empty for strategy '{}'", self.name));
-        }
+    levels
+        .iter()
+        .enumerate()
+        .filter(|(_, level)| **level <= 0.0 || **level >= 100.0)
+        .map(|(i, level)| {
+            Error::invalid_field(
+                format!("{}[{}]", field, i),
+                format!("Invalid grid level: {}. Must be between 0 and 100", level),
+            )
+        })
+        .collect()
+}
 
-        for level in &self.levels {
-            if *level <= 0.0 || *level >= 100.0 {
-                return Err(format!("Invalid grid level in strategy '{}': {}. Must be between 0 and 100", self.name, level));
-            }
-        }
+/// Check that a positive multiplier is present when the position mode
+/// needs one
+pub fn validate_multiplier(mode: PositionMode, multiplier: Option<f64>) -> Result<()> {
+    first_error(multiplier_errors(mode, multiplier, "position.multiplier"))
+}
+
+/// Check that grid levels are non-empty percentages between 0 and 100.
+/// `field` is the path of the levels list, used in errors.
+pub fn validate_levels(levels: &[f64], field: &str) -> Result<()> {
+    first_error(level_errors(levels, field))
+}
 
-        Ok(())
+impl Strategy {
+    /// Validate the merged config this strategy resolves to
+    pub fn validate(&self, config: &Config) -> Result<()> {
+        self.to_config(config)?
+            .validate()
+            .map_err(|e| e.in_strategy(&self.name))
     }
+}
 
-    pub fn to_config(&self) -> Config {
+#[cfg(test)]
+impl Config {
+    /// `simple_config.toml` with the ladder and position sizing replaced,
+    /// for tests that only care about those values
+    pub(crate) fn test(
+        levels: &[f64],
+        mode: PositionMode,
+        base_size: f64,
+        multiplier: Option<f64>,
+    ) -> Config {
         Config {
-            base: BaseConfig {
-                initial_price: self.initial_price,
-                grid_type: self.grid_type.clone(),
-            },
             grid: GridConfig {
-                levels: self.levels.clone(),
+                levels: levels.to_vec(),
             },
             position: PositionConfig {
-                mode: self.position_mode.clone(),
-                base_size: self.base_size,
-                multiplier: self.multiplier,
+                mode,
+                base_size,
+                multiplier,
             },
-            strategies: None,
+            ..toml::from_str(include_str!("../simple_config.toml")).unwrap()
         }
     }
 }
@@ -145,17 +396,22 @@ mod tests {
         let config = Config {
             base: BaseConfig {
                 initial_price: 100.0,
-                grid_type: "fixed".to_string(),
+                grid_type: GridType::Fixed,
             },
             grid: GridConfig {
                 levels: vec![1.0, 2.0, 3.0],
             },
             position: PositionConfig {
-                mode: "fixed".to_string(),
+                mode: PositionMode::Fixed,
                 base_size: 100.0,
                 multiplier: None,
             },
+            version: migrate::CURRENT_VERSION,
+            include: None,
             strategies: None,
+            lints: None,
+            alerts: None,
+            portfolio: None,
         };
 
         assert!(config.validate().is_ok());
@@ -163,43 +419,128 @@ mod tests {
 
     #[test]
     fn test_invalid_grid_type() {
+        let contents = r#"
+[base]
+initial_price = 100.0
+grid_type = "invalid"
+
+[grid]
+levels = [1.0, 2.0, 3.0]
+
+[position]
+mode = "fixed"
+base_size = 100.0
+"#;
+
+        let err = toml::from_str::<Config>(contents).unwrap_err().to_string();
+        assert!(err.contains("line 4, column 13"), "{}", err);
+        assert!(err.contains("unknown variant `invalid`"), "{}", err);
+    }
+
+    #[test]
+    fn test_missing_multiplier() {
         let config = Config {
             base: BaseConfig {
                 initial_price: 100.0,
-                grid_type: "invalid".to_string(),
+                grid_type: GridType::Fixed,
             },
             grid: GridConfig {
                 levels: vec![1.0, 2.0, 3.0],
             },
             position: PositionConfig {
-                mode: "fixed".to_string(),
+                mode: PositionMode::CurrentMultiple,
                 base_size: 100.0,
                 multiplier: None,
             },
+            version: migrate::CURRENT_VERSION,
+            include: None,
             strategies: None,
+            lints: None,
+            alerts: None,
+            portfolio: None,
         };
 
-        assert!(config.validate().is_err());
+        assert!(matches!(
+            config.validate(),
+            Err(Error::Constraint { strategy: None, .. })
+        ));
     }
 
     #[test]
-    fn test_missing_multiplier() {
-        let config = Config {
-            base: BaseConfig {
-                initial_price: 100.0,
-                grid_type: "fixed".to_string(),
-            },
-            grid: GridConfig {
-                levels: vec![1.0, 2.0, 3.0],
+    fn test_non_positive_multiplier() {
+        for multiplier in [0.0, -2.0] {
+            let errors = multiplier_errors(
+                PositionMode::CurrentMultiple,
+                Some(multiplier),
+                "position.multiplier",
+            );
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].field(), Some("position.multiplier"));
+        }
+        assert!(
+            multiplier_errors(PositionMode::Fixed, Some(-2.0), "position.multiplier").is_empty()
+        );
+    }
+
+    #[test]
+    fn test_inherited_strategy_errors() {
+        let mut config = Config::test(&[1.0, 2.0], PositionMode::Fixed, 100.0, Some(-1.0));
+        config.strategies = Some(vec![Strategy {
+            name: "s".to_string(),
+            extends: Some(MAIN_CONFIG.to_string()),
+            base: PartialBase::default(),
+            grid: PartialGrid::default(),
+            position: PartialPosition {
+                mode: Some(PositionMode::CurrentMultiple),
+                ..PartialPosition::default()
             },
-            position: PositionConfig {
-                mode: "current-multiple".to_string(),
-                base_size: 100.0,
-                multiplier: None,
+        }]);
+        assert!(config.validate().is_ok());
+        let resolved = config.resolve_strategy("s").unwrap();
+        assert!(resolved.config.validate().is_err());
+
+        // The main config is valid at the multiplier, so the strategy's
+        // error is reported there instead of being dropped
+        let diagnostics = config.diagnostics();
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].path.as_deref(), Some("position.multiplier"));
+        assert!(diagnostics[0].message.contains("strategy 's'"));
+    }
+
+    #[test]
+    fn test_strategy_errors() {
+        let strategy = Strategy {
+            name: "wide".to_string(),
+            extends: Some(MAIN_CONFIG.to_string()),
+            base: PartialBase::default(),
+            grid: PartialGrid {
+                levels: Some(vec![1.0, 120.0]),
             },
-            strategies: None,
+            position: PartialPosition::default(),
         };
+        let config = Config {
+            strategies: Some(vec![strategy.clone()]),
+            ..toml::from_str(include_str!("../simple_config.toml")).unwrap()
+        };
+
+        match strategy.validate(&config) {
+            Err(Error::InvalidField {
+                field, strategy, ..
+            }) => {
+                assert_eq!(field, "grid.levels[1]");
+                assert_eq!(strategy.as_deref(), Some("wide"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
 
-        assert!(config.validate().is_err());
+        assert!(config.strategy("wide").is_ok());
+        assert!(matches!(
+            config.strategy("missing"),
+            Err(Error::StrategyNotFound { .. })
+        ));
+        assert!(matches!(
+            Config::from_file("does-not-exist.toml"),
+            Err(Error::Io { .. })
+        ));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,149 @@
+use super::{migrate, Config};
+use crate::error::{Error, Result};
+use clap::ValueEnum;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// File format of a config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Format::Toml => "toml",
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+        }
+    }
+
+    /// Format named by a file's extension, if it is one we know
+    pub fn from_extension(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "toml" => Some(Format::Toml),
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Format of `path` by extension, falling back to TOML
+    pub fn detect(path: &Path) -> Format {
+        Format::from_extension(path).unwrap_or(Format::Toml)
+    }
+
+    /// Parse `contents` in this format. Errors carry the parser's message,
+    /// including the location where the format reports one.
+    pub fn parse<T: DeserializeOwned>(&self, contents: &str) -> Result<T> {
+        let result = match self {
+            Format::Toml => toml::from_str(contents).map_err(|e| e.to_string()),
+            Format::Json => serde_json::from_str(contents).map_err(|e| e.to_string()),
+            Format::Yaml => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+        };
+        result.map_err(Error::parse)
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<String> {
+        let result = match self {
+            Format::Toml => toml::to_string_pretty(value).map_err(|e| e.to_string()),
+            Format::Json => serde_json::to_string_pretty(value)
+                .map(|s| s + "\n")
+                .map_err(|e| e.to_string()),
+            Format::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+        };
+        result.map_err(|message| Error::Serialize { message })
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Config {
+    /// Read a config in the given format, upgrading older versions
+    pub fn from_file_as<P: AsRef<Path>>(path: P, format: Format) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        migrate::parse(&contents, format).map_err(|e| e.in_file(path))
+    }
+
+    /// Write a config in the given format
+    pub fn to_file_as<P: AsRef<Path>>(&self, path: P, format: Format) -> Result<()> {
+        let path = path.as_ref();
+        let contents = format.serialize(self)?;
+        fs::write(path, contents).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str(include_str!("../../config_example.toml")).unwrap()
+    }
+
+    #[test]
+    fn test_detect_by_extension() {
+        assert_eq!(Format::detect(Path::new("a.json")), Format::Json);
+        assert_eq!(Format::detect(Path::new("dir/a.YML")), Format::Yaml);
+        assert_eq!(Format::detect(Path::new("a.yaml")), Format::Yaml);
+        assert_eq!(Format::detect(Path::new("a.toml")), Format::Toml);
+        assert_eq!(Format::detect(Path::new("config")), Format::Toml);
+        assert_eq!(Format::from_extension(Path::new("a.txt")), None);
+    }
+
+    #[test]
+    fn test_round_trip_every_format() {
+        let original = config();
+        let expected = Format::Toml.serialize(&original).unwrap();
+
+        for format in Format::value_variants() {
+            let text = format.serialize(&original).unwrap();
+            let parsed: Config = format.parse(&text).unwrap();
+            assert_eq!(
+                Format::Toml.serialize(&parsed).unwrap(),
+                expected,
+                "{}",
+                format
+            );
+
+            // Converting through another format keeps every value
+            for other in Format::value_variants() {
+                let converted: Config = other.parse(&other.serialize(&parsed).unwrap()).unwrap();
+                assert_eq!(Format::Toml.serialize(&converted).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_have_locations() {
+        let err = Format::Json
+            .parse::<Config>("{\"base\": {\"initial_price\": \"high\"}}")
+            .unwrap_err();
+        assert!(err.to_string().contains("line 1"), "{}", err);
+
+        let yaml = "base:\n  initial_price: 100.0\n  grid_type: sideways\n";
+        let err = Format::Yaml.parse::<Config>(yaml).unwrap_err();
+        assert!(
+            err.to_string().contains("unknown variant `sideways`"),
+            "{}",
+            err
+        );
+    }
+}
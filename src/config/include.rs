@@ -0,0 +1,399 @@
+use super::format::Format;
+use super::migrate;
+use super::{Config, Strategy};
+use crate::diagnostics::Diagnostic;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A file pulled in through `include`. It holds strategies only; the
+/// `[base]`, `[grid]` and `[position]` tables belong to the main config.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Library {
+    #[serde(default = "migrate::current_version")]
+    version: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    #[serde(default)]
+    strategies: Vec<Strategy>,
+}
+
+/// A config merged from its main file and every file it includes
+#[derive(Debug, Clone)]
+pub struct Loaded {
+    pub config: Config,
+    /// File the main tables came from
+    pub path: PathBuf,
+    /// For each merged strategy, the file it came from and its index in
+    /// that file's `[[strategies]]`
+    pub origins: Vec<(PathBuf, usize)>,
+}
+
+impl Loaded {
+    /// Fail if two files define a strategy with the same name. Duplicates
+    /// within one file are left to `Config::diagnostics`.
+    pub fn check_duplicates(&self) -> Result<()> {
+        let strategies = self.config.strategies.as_deref().unwrap_or_default();
+        for (i, strategy) in strategies.iter().enumerate() {
+            let first = strategies[..i]
+                .iter()
+                .zip(&self.origins)
+                .find(|(s, (file, _))| s.name == strategy.name && *file != self.origins[i].0);
+            if let Some((_, (file, _))) = first {
+                return Err(Error::invalid_field(
+                    "name",
+                    format!(
+                        "Duplicate strategy name '{}' in {} and {}",
+                        strategy.name,
+                        file.display(),
+                        self.origins[i].0.display()
+                    ),
+                )
+                .in_strategy(&strategy.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// Group diagnostics of the merged config by the file they belong to,
+    /// rewriting `strategies[i]` paths to the index within that file. The
+    /// main file comes first, then included files in load order.
+    pub fn by_file(&self, diagnostics: Vec<Diagnostic>) -> Vec<(PathBuf, Vec<Diagnostic>)> {
+        let mut groups: Vec<(PathBuf, Vec<Diagnostic>)> = vec![(self.path.clone(), Vec::new())];
+        for (file, _) in &self.origins {
+            if !groups.iter().any(|(f, _)| f == file) {
+                groups.push((file.clone(), Vec::new()));
+            }
+        }
+
+        for mut diagnostic in diagnostics {
+            let mut file = &self.path;
+            if let Some((index, rest)) = diagnostic.path.as_deref().and_then(split_strategy) {
+                if let Some((origin, local)) = self.origins.get(index) {
+                    file = origin;
+                    diagnostic.path = Some(format!("strategies[{}]{}", local, rest));
+                }
+            }
+            if let Some((_, group)) = groups.iter_mut().find(|(f, _)| f == file) {
+                group.push(diagnostic);
+            }
+        }
+        groups
+    }
+}
+
+fn split_strategy(path: &str) -> Option<(usize, String)> {
+    let rest = path.strip_prefix("strategies[")?;
+    let end = rest.find(']')?;
+    Some((rest[..end].parse().ok()?, rest[end + 1..].to_string()))
+}
+
+impl Config {
+    /// Load a config file or directory, merging the strategies of every
+    /// included file. Fails if two files define the same strategy name.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let loaded = load(path.as_ref(), None)?;
+        loaded.check_duplicates()?;
+        Ok(loaded.config)
+    }
+}
+
+/// Load a config with its includes. `path` is either a config file or a
+/// directory; in a directory, the one config file with a `[base]` table is
+/// the main config and every other config file is a strategy library. See
+/// `config_files` for which files in a directory count.
+/// `format` overrides the main file's format; included files are always
+/// detected by extension.
+pub fn load(path: &Path, format: Option<Format>) -> Result<Loaded> {
+    let (main, libraries) = if path.is_dir() {
+        split_directory(path)?
+    } else {
+        (path.to_path_buf(), Vec::new())
+    };
+
+    let config = Config::from_file_as(&main, format.unwrap_or_else(|| Format::detect(&main)))?;
+    let include = config.include.clone().unwrap_or_default();
+    let own = config.strategies.as_ref().map_or(0, Vec::len);
+    let mut loaded = Loaded {
+        config,
+        origins: (0..own).map(|i| (main.clone(), i)).collect(),
+        path: main.clone(),
+    };
+
+    let mut seen = HashSet::from([canonical(&main)]);
+    let base = main.parent().unwrap_or(Path::new(""));
+    for file in expand(&main, base, &include)?.into_iter().chain(libraries) {
+        merge(&mut loaded, &file, &mut seen)?;
+    }
+    Ok(loaded)
+}
+
+fn merge(loaded: &mut Loaded, path: &Path, seen: &mut HashSet<PathBuf>) -> Result<()> {
+    if !seen.insert(canonical(path)) {
+        return Ok(());
+    }
+
+    let library: Library = parse(path)?;
+
+    let strategies = loaded.config.strategies.get_or_insert_with(Vec::new);
+    for (i, strategy) in library.strategies.into_iter().enumerate() {
+        strategies.push(strategy);
+        loaded.origins.push((path.to_path_buf(), i));
+    }
+
+    let base = path.parent().unwrap_or(Path::new(""));
+    for file in expand(path, base, &library.include)? {
+        merge(loaded, &file, seen)?;
+    }
+    Ok(())
+}
+
+/// Resolve `include` patterns relative to the including file's directory.
+/// Patterns without wildcards name a single file that must exist.
+fn expand(file: &Path, base: &Path, patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for pattern in patterns {
+        let full = base.join(pattern);
+        if !pattern.contains(['*', '?', '[']) {
+            paths.push(full);
+            continue;
+        }
+
+        let invalid = |message: String| Error::Parse {
+            path: Some(file.to_path_buf()),
+            message: format!("Invalid include pattern '{}': {}", pattern, message),
+        };
+        let matches = glob::glob(&full.to_string_lossy()).map_err(|e| invalid(e.to_string()))?;
+        let mut matched = matches
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| invalid(e.to_string()))?;
+        matched.sort();
+        paths.extend(matched);
+    }
+    Ok(paths)
+}
+
+/// Config files in a directory, sorted by name. A file counts if it has a
+/// TOML, JSON or YAML extension and its top level has a `[base]` table (a
+/// main config) or `strategies` or `include` (a strategy library). Other
+/// files, such as the `grid-state.json` that `start` writes, are skipped.
+pub fn config_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(scan(dir)?.into_iter().map(|(file, _)| file).collect())
+}
+
+/// Config files in a directory with whether each is a main config
+fn scan(dir: &Path) -> Result<Vec<(PathBuf, bool)>> {
+    let entries = fs::read_dir(dir).map_err(|source| Error::Io {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.is_file() && Format::from_extension(path).is_some())
+        .collect();
+    files.sort();
+
+    let mut configs = Vec::new();
+    for file in files {
+        let value: serde_json::Value = Format::detect(&file)
+            .parse(&read(&file)?)
+            .map_err(|e| e.in_file(&file))?;
+        let Some(table) = value.as_object() else {
+            continue;
+        };
+        let main = table.contains_key("base");
+        if main || table.contains_key("strategies") || table.contains_key("include") {
+            configs.push((file, main));
+        }
+    }
+    Ok(configs)
+}
+
+/// Find the main config in a directory and the strategy libraries next to it
+fn split_directory(dir: &Path) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let (mains, libraries): (Vec<_>, Vec<_>) = scan(dir)?.into_iter().partition(|(_, main)| *main);
+    let mains: Vec<PathBuf> = mains.into_iter().map(|(file, _)| file).collect();
+    let libraries = libraries.into_iter().map(|(file, _)| file).collect();
+
+    let main = match mains.as_slice() {
+        [main] => main.clone(),
+        [] => {
+            return Err(Error::Parse {
+                path: Some(dir.to_path_buf()),
+                message: "No config file with a [base] table in directory".to_string(),
+            })
+        }
+        _ => {
+            let names: Vec<String> = mains.iter().map(|p| p.display().to_string()).collect();
+            return Err(Error::Parse {
+                path: Some(dir.to_path_buf()),
+                message: format!(
+                    "Several config files with a [base] table: {}",
+                    names.join(", ")
+                ),
+            });
+        }
+    };
+    Ok((main, libraries))
+}
+
+fn parse<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    migrate::parse(&read(path)?, Format::detect(path)).map_err(|e| e.in_file(path))
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAIN: &str = r#"
+include = ["strategies/*.toml"]
+
+[base]
+initial_price = 100.0
+grid_type = "fixed"
+
+[grid]
+levels = [1.0, 2.0, 3.0]
+
+[position]
+mode = "fixed"
+base_size = 100.0
+
+[[strategies]]
+name = "local"
+extends = "main"
+"#;
+
+    fn write(dir: &Path, name: &str, contents: &str) {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("grid-include-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_include_glob() {
+        let dir = temp_dir("glob");
+        write(&dir, "config.toml", MAIN);
+        write(
+            &dir,
+            "strategies/a.toml",
+            "[[strategies]]\nname = \"wide\"\nextends = \"local\"\ngrid.levels = [5.0, 10.0]\n",
+        );
+        write(&dir, "strategies/b.toml", "[[strategies]]\nname = \"tight\"\nextends = \"main\"\n\n[[strategies]]\nname = \"bad\"\nextends = \"main\"\ngrid.levels = [0.0]\n");
+
+        let loaded = load(&dir.join("config.toml"), None).unwrap();
+        let names: Vec<_> = loaded
+            .config
+            .strategies
+            .iter()
+            .flatten()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["local", "wide", "tight", "bad"]);
+        assert_eq!(
+            loaded
+                .config
+                .resolve_strategy("wide")
+                .unwrap()
+                .config
+                .grid
+                .levels,
+            vec![5.0, 10.0]
+        );
+        assert!(loaded.check_duplicates().is_ok());
+
+        let groups = loaded.by_file(loaded.config.diagnostics());
+        assert_eq!(groups.len(), 3);
+        let (file, diagnostics) = &groups[2];
+        assert!(file.ends_with("strategies/b.toml"));
+        assert_eq!(
+            diagnostics[0].path.as_deref(),
+            Some("strategies[1].grid.levels[0]")
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_duplicates_across_files() {
+        let dir = temp_dir("duplicates");
+        write(&dir, "config.toml", MAIN);
+        write(
+            &dir,
+            "strategies/a.toml",
+            "[[strategies]]\nname = \"local\"\nextends = \"main\"\n",
+        );
+
+        let err = Config::load(dir.join("config.toml")).unwrap_err();
+        assert!(
+            err.to_string().contains("Duplicate strategy name 'local'"),
+            "{}",
+            err
+        );
+        assert!(err.to_string().contains("a.toml"), "{}", err);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_directory() {
+        let dir = temp_dir("directory");
+        write(
+            &dir,
+            "main.toml",
+            &MAIN.replace("include = [\"strategies/*.toml\"]", ""),
+        );
+        write(
+            &dir,
+            "extra.json",
+            r#"{"strategies": [{"name": "extra", "extends": "local"}]}"#,
+        );
+
+        let loaded = load(&dir, None).unwrap();
+        assert!(loaded.path.ends_with("main.toml"));
+        assert_eq!(loaded.origins[1], (dir.join("extra.json"), 0));
+        assert!(loaded.config.resolve_strategy("extra").is_ok());
+
+        // Files that are neither a main config nor a library are skipped
+        write(
+            &dir,
+            "grid-state.json",
+            r#"{"strategy": null, "config": {}}"#,
+        );
+        write(&dir, "prices.json", "[100.0, 99.5]");
+        let loaded = load(&dir, None).unwrap();
+        assert_eq!(loaded.origins.len(), 2);
+        assert_eq!(
+            config_files(&dir).unwrap(),
+            vec![dir.join("extra.json"), dir.join("main.toml")]
+        );
+
+        write(&dir, "other.toml", MAIN);
+        let err = load(&dir, None).unwrap_err();
+        assert!(err.to_string().contains("Several config files"), "{}", err);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
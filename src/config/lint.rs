@@ -0,0 +1,311 @@
+use crate::config::Config;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::grid_calculator::{GridCalculator, GridType, PositionMode};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Multiplier at which `current-multiple` sizing is considered aggressive
+pub const AGGRESSIVE_MULTIPLIER: f64 = 2.0;
+
+/// Number of levels from which an aggressive multiplier is reported
+pub const AGGRESSIVE_LEVEL_COUNT: usize = 5;
+
+/// Checks for configs that are valid but likely to be a mistake or risky
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Lint {
+    /// Grid levels are not in ascending order
+    UnsortedLevels,
+    /// The same grid level appears more than once
+    DuplicateLevels,
+    /// `current-multiple` with a large multiplier over many levels
+    AggressiveMultiplier,
+    /// `initial_price` is zero or negative
+    NonPositivePrice,
+    /// `base_size` is negative
+    NegativeBaseSize,
+    /// A multiplier is set for the `fixed` mode, which ignores it
+    IgnoredMultiplier,
+}
+
+/// How a lint finding is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintLevel {
+    /// Do not report the lint
+    Allow,
+    /// Report the lint as a warning
+    Warn,
+    /// Report the lint as an error
+    Deny,
+}
+
+impl Lint {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Lint::UnsortedLevels => "unsorted-levels",
+            Lint::DuplicateLevels => "duplicate-levels",
+            Lint::AggressiveMultiplier => "aggressive-multiplier",
+            Lint::NonPositivePrice => "non-positive-price",
+            Lint::NegativeBaseSize => "negative-base-size",
+            Lint::IgnoredMultiplier => "ignored-multiplier",
+        }
+    }
+
+    /// Level used when the config does not override it
+    pub fn default_level(&self) -> LintLevel {
+        LintLevel::Warn
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Run every lint over the main config and all strategies. Levels come from
+/// the config's `[lints]` table; allowed lints are not reported.
+pub fn lint(config: &Config) -> Vec<Diagnostic> {
+    let mut findings = Vec::new();
+    check(config, None, &mut findings);
+
+    // Multipliers a strategy inherits into a multiplier mode are not ignored
+    let mut used_multipliers = Vec::new();
+    for (i, strategy) in config.strategies.iter().flatten().enumerate() {
+        // Strategies that fail to resolve are reported by validation
+        let Ok(resolved) = strategy.resolve(config) else {
+            continue;
+        };
+        if resolved.config.position.mode.uses_multiplier() {
+            used_multipliers.extend(resolved.defined_at(config, "position.multiplier"));
+        }
+        let mut strategy_findings = Vec::new();
+        check(
+            &resolved.config,
+            Some(&strategy.name),
+            &mut strategy_findings,
+        );
+
+        // Inherited values are linted where they are defined
+        findings.extend(
+            strategy_findings
+                .into_iter()
+                .filter_map(|(lint, path, message)| {
+                    Some((lint, resolved.own_path(i, &path)?, message))
+                }),
+        );
+    }
+
+    findings
+        .into_iter()
+        .filter(|(lint, path, _)| {
+            *lint != Lint::IgnoredMultiplier || !used_multipliers.contains(path)
+        })
+        .filter_map(|(lint, path, message)| {
+            let severity = match config.lint_level(lint) {
+                LintLevel::Allow => return None,
+                LintLevel::Warn => Severity::Warning,
+                LintLevel::Deny => Severity::Error,
+            };
+            Some(Diagnostic {
+                severity,
+                path: Some(path),
+                message: format!("{} [{}]", message, lint),
+                span: None,
+            })
+        })
+        .collect()
+}
+
+fn check(config: &Config, strategy: Option<&str>, findings: &mut Vec<(Lint, String, String)>) {
+    let in_strategy = match strategy {
+        Some(name) => format!(" in strategy '{}'", name),
+        None => String::new(),
+    };
+
+    let levels = &config.grid.levels;
+    if let Some(i) = (1..levels.len()).find(|&i| levels[i] < levels[i - 1]) {
+        findings.push((
+            Lint::UnsortedLevels,
+            format!("grid.levels[{}]", i),
+            format!(
+                "Grid level {} comes after {}{}; levels are applied in file order",
+                levels[i],
+                levels[i - 1],
+                in_strategy
+            ),
+        ));
+    }
+    for (i, level) in levels.iter().enumerate() {
+        if levels[..i].contains(level) {
+            findings.push((
+                Lint::DuplicateLevels,
+                format!("grid.levels[{}]", i),
+                format!("Grid level {} appears more than once{}", level, in_strategy),
+            ));
+        }
+    }
+
+    match (config.position.mode, config.position.multiplier) {
+        (PositionMode::CurrentMultiple, Some(mult))
+            if mult >= AGGRESSIVE_MULTIPLIER && levels.len() >= AGGRESSIVE_LEVEL_COUNT =>
+        {
+            // Size the ladder with a unit base to show how fast exposure grows
+            let mut calculator = GridCalculator::new(
+                1.0,
+                GridType::Fixed,
+                PositionMode::CurrentMultiple,
+                1.0,
+                mult,
+            );
+            let growth = levels
+                .iter()
+                .map(|level| calculator.calculate_grid(*level).total_position)
+                .last()
+                .unwrap_or(1.0);
+            findings.push((
+                Lint::AggressiveMultiplier,
+                "position.multiplier".to_string(),
+                format!(
+                    "current-multiple with multiplier {} over {} levels grows the position to {:.0}x the base size{}",
+                    mult,
+                    levels.len(),
+                    growth,
+                    in_strategy
+                ),
+            ));
+        }
+        (PositionMode::Fixed, Some(mult)) => findings.push((
+            Lint::IgnoredMultiplier,
+            "position.multiplier".to_string(),
+            format!(
+                "Multiplier {} is ignored in 'fixed' position mode{}",
+                mult, in_strategy
+            ),
+        )),
+        _ => {}
+    }
+
+    let price = config.base.initial_price;
+    if price <= 0.0 {
+        findings.push((
+            Lint::NonPositivePrice,
+            "base.initial_price".to_string(),
+            format!(
+                "initial_price is {}{}; every grid price will be zero or negative",
+                price, in_strategy
+            ),
+        ));
+    }
+
+    let size = config.position.base_size;
+    if size < 0.0 {
+        findings.push((
+            Lint::NegativeBaseSize,
+            "position.base_size".to_string(),
+            format!(
+                "base_size is {}{}; the ladder will sell instead of buy",
+                size, in_strategy
+            ),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn config(source: &str) -> Config {
+        toml::from_str(source).unwrap()
+    }
+
+    const RISKY: &str = r#"
+[base]
+initial_price = 0.0
+grid_type = "fixed"
+
+[grid]
+levels = [1.0, 3.0, 2.0, 3.0, 5.0]
+
+[position]
+mode = "current-multiple"
+base_size = -100.0
+multiplier = 2.0
+
+[[strategies]]
+name = "calm"
+base = { initial_price = 100.0, grid_type = "fixed" }
+grid = { levels = [1.0, 2.0] }
+position = { mode = "fixed", base_size = 100.0, multiplier = 1.5 }
+"#;
+
+    #[test]
+    fn test_reports_each_lint() {
+        let diagnostics = lint(&config(RISKY));
+        let paths: Vec<_> = diagnostics
+            .iter()
+            .map(|d| d.path.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "grid.levels[2]",
+                "grid.levels[3]",
+                "position.multiplier",
+                "base.initial_price",
+                "position.base_size",
+                "strategies[0].position.multiplier",
+            ]
+        );
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Warning));
+        assert!(diagnostics[2].message.contains("81x the base size"));
+        assert!(diagnostics[5].message.contains("in strategy 'calm'"));
+    }
+
+    #[test]
+    fn test_inherited_multiplier_is_not_ignored() {
+        let mut cfg = config(RISKY);
+        cfg.position.mode = PositionMode::Fixed;
+        cfg.strategies = Some(vec![toml::from_str(
+            "name = \"s\"\nextends = \"main\"\nposition = { mode = \"current-multiple\" }",
+        )
+        .unwrap()]);
+
+        let diagnostics = lint(&cfg);
+        assert!(diagnostics
+            .iter()
+            .all(|d| !d.message.ends_with("[ignored-multiplier]")));
+    }
+
+    #[test]
+    fn test_lint_levels() {
+        let mut cfg = config(RISKY);
+        cfg.lints = Some(BTreeMap::from([
+            (Lint::NonPositivePrice, LintLevel::Deny),
+            (Lint::IgnoredMultiplier, LintLevel::Allow),
+            (Lint::DuplicateLevels, LintLevel::Allow),
+        ]));
+
+        let diagnostics = lint(&cfg);
+        assert_eq!(diagnostics.len(), 4);
+        let denied: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect();
+        assert_eq!(denied.len(), 1);
+        assert!(denied[0].message.ends_with("[non-positive-price]"));
+    }
+
+    #[test]
+    fn test_lint_table_in_toml() {
+        let source = format!("{}\n[lints]\nunsorted-levels = \"allow\"\n", RISKY);
+        let cfg = config(&source);
+        assert_eq!(cfg.lint_level(Lint::UnsortedLevels), LintLevel::Allow);
+        assert_eq!(cfg.lint_level(Lint::DuplicateLevels), LintLevel::Warn);
+    }
+}
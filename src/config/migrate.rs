@@ -0,0 +1,452 @@
+use super::format::Format;
+use super::include::Library;
+use super::Config;
+use crate::error::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, InlineTable, Item, Table, TableLike, Value};
+
+/// Version of the config shape this build reads and writes
+pub const CURRENT_VERSION: u32 = 3;
+
+/// Version of files written before the `version` field existed
+pub const UNVERSIONED: u32 = 1;
+
+pub(crate) fn current_version() -> u32 {
+    CURRENT_VERSION
+}
+
+/// One upgrade step from version `from` to `from + 1`
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: fn(&mut Table),
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        description: "Spell grid types and position modes as lowercase kebab-case names",
+        apply: normalize_mode_names,
+    },
+    Migration {
+        from: 2,
+        description: "Move strategy fields into base, grid and position sections",
+        apply: nest_strategy_fields,
+    },
+];
+
+/// Read a config or strategy library as an editable TOML document. JSON
+/// and YAML are converted, dropping null values since TOML has no null;
+/// a null reads the same as a missing field.
+pub(crate) fn document(contents: &str, format: Format) -> Result<DocumentMut> {
+    let table: toml::Table = match format {
+        Format::Toml => {
+            return contents
+                .parse()
+                .map_err(|e: toml_edit::TomlError| Error::parse(e.to_string()))
+        }
+        Format::Json => {
+            let mut value: serde_json::Value =
+                serde_json::from_str(contents).map_err(|e| Error::parse(e.to_string()))?;
+            strip_json_nulls(&mut value);
+            serde_json::from_value(value).map_err(|e| Error::parse(e.to_string()))?
+        }
+        Format::Yaml => {
+            let mut value: serde_yaml::Value =
+                serde_yaml::from_str(contents).map_err(|e| Error::parse(e.to_string()))?;
+            strip_yaml_nulls(&mut value);
+            serde_yaml::from_value(value).map_err(|e| Error::parse(e.to_string()))?
+        }
+    };
+    toml::to_string(&table)
+        .map_err(|e| Error::parse(e.to_string()))?
+        .parse()
+        .map_err(|e: toml_edit::TomlError| Error::parse(e.to_string()))
+}
+
+fn strip_json_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_json_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_json_nulls),
+        _ => {}
+    }
+}
+
+fn strip_yaml_nulls(value: &mut serde_yaml::Value) {
+    match value {
+        serde_yaml::Value::Mapping(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_yaml_nulls);
+        }
+        serde_yaml::Value::Sequence(items) => items.iter_mut().for_each(strip_yaml_nulls),
+        _ => {}
+    }
+}
+
+/// Version a config document declares, or `UNVERSIONED` if it has none
+pub fn version_of(document: &Table) -> Result<u32> {
+    let Some(item) = document.get("version") else {
+        return Ok(UNVERSIONED);
+    };
+    match item.as_integer() {
+        Some(v) if v >= 1 && v <= u32::MAX as i64 => Ok(v as u32),
+        _ => Err(Error::invalid_field(
+            "version",
+            format!("Invalid config version: {}", item.to_string().trim()),
+        )),
+    }
+}
+
+/// Upgrade a config or strategy library document to `CURRENT_VERSION`.
+/// Comments and layout are kept. Returns a description of every step
+/// applied, oldest first.
+pub fn migrate(document: &mut Table) -> Result<Vec<&'static str>> {
+    let mut version = version_of(document)?;
+    if version > CURRENT_VERSION {
+        return Err(newer_version(version));
+    }
+
+    let mut steps = Vec::new();
+    while version < CURRENT_VERSION {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.from == version)
+            .expect("every older version has a migration");
+        (migration.apply)(document);
+        steps.push(migration.description);
+        version += 1;
+    }
+    match document.get_mut("version") {
+        Some(item) => set_value(item, version as i64),
+        None => {
+            document.insert("version", toml_edit::value(version as i64));
+        }
+    }
+    Ok(steps)
+}
+
+pub(crate) fn newer_version(version: u32) -> Error {
+    Error::invalid_field(
+        "version",
+        format!(
+            "Config version {} is newer than the supported version {}",
+            version, CURRENT_VERSION
+        ),
+    )
+}
+
+/// Parse a document in `format`, upgrading older versions first. Current
+/// documents are parsed directly so errors keep their location.
+pub(crate) fn parse<T: DeserializeOwned>(contents: &str, format: Format) -> Result<T> {
+    let mut document = document(contents, format)?;
+    if version_of(&document)? == CURRENT_VERSION {
+        return format.parse(contents);
+    }
+    migrate(&mut document)?;
+    toml::from_str(&document.to_string()).map_err(|e| Error::parse(e.message()))
+}
+
+/// Result of upgrading a file with `migrate_file`
+#[derive(Debug)]
+pub struct Migrated {
+    pub from: u32,
+    pub steps: Vec<&'static str>,
+    /// Copy of the original file
+    pub backup: PathBuf,
+}
+
+/// Upgrade a config or strategy library file in place. The original is
+/// kept next to it as `<file>.v<version>.bak`. TOML files are edited so
+/// their comments and layout survive; JSON and YAML are rewritten. Returns
+/// `None` if the file is already current.
+pub fn migrate_file(path: &Path, format: Format) -> Result<Option<Migrated>> {
+    let contents = fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let mut document = document(&contents, format).map_err(|e| e.in_file(path))?;
+    let from = version_of(&document)?;
+    if from == CURRENT_VERSION {
+        return Ok(None);
+    }
+    let steps = migrate(&mut document)?;
+    let output = if document.contains_key("base") {
+        rewrite::<Config>(&document, format, path)?
+    } else {
+        rewrite::<Library>(&document, format, path)?
+    };
+
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", from));
+    let backup = PathBuf::from(backup);
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |source| Error::Io { path, source }
+    };
+    fs::copy(path, &backup).map_err(io_error(&backup))?;
+    fs::write(path, output).map_err(io_error(path))?;
+
+    Ok(Some(Migrated {
+        from,
+        steps,
+        backup,
+    }))
+}
+
+/// Text of an upgraded document in `format`, checked against the current
+/// shape before the file is touched
+fn rewrite<T: DeserializeOwned + Serialize>(
+    document: &DocumentMut,
+    format: Format,
+    path: &Path,
+) -> Result<String> {
+    let text = document.to_string();
+    let value: T = toml::from_str(&text).map_err(|e| Error::Parse {
+        path: Some(path.to_path_buf()),
+        message: e.message().to_string(),
+    })?;
+    match format {
+        Format::Toml => Ok(text),
+        Format::Json | Format::Yaml => format.serialize(&value),
+    }
+}
+
+/// Replace a value, keeping the whitespace and comments around it
+fn set_value(item: &mut Item, value: impl Into<Value>) {
+    match item.as_value_mut() {
+        Some(old) => {
+            let decor = old.decor().clone();
+            *old = value.into();
+            *old.decor_mut() = decor;
+        }
+        None => *item = Item::Value(value.into()),
+    }
+}
+
+/// Strategies as `[[strategies]]` tables or an inline array of tables
+fn strategies(document: &mut Table) -> Vec<&mut dyn TableLike> {
+    match document.get_mut("strategies") {
+        Some(Item::ArrayOfTables(tables)) => {
+            tables.iter_mut().map(|t| t as &mut dyn TableLike).collect()
+        }
+        Some(Item::Value(Value::Array(array))) => array
+            .iter_mut()
+            .filter_map(Value::as_inline_table_mut)
+            .map(|t| t as &mut dyn TableLike)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Version 1 stored modes as free-form strings; accept other casings and
+/// separators, e.g. `Current_Multiple`
+fn normalize_mode_names(document: &mut Table) {
+    fn normalize(table: Option<&mut dyn TableLike>, key: &str) {
+        if let Some(item) = table.and_then(|t| t.get_mut(key)) {
+            if let Some(name) = item.as_str() {
+                let name = name.trim().to_lowercase().replace(['_', ' '], "-");
+                set_value(item, name);
+            }
+        }
+    }
+
+    normalize(
+        document.get_mut("base").and_then(Item::as_table_like_mut),
+        "grid_type",
+    );
+    normalize(
+        document
+            .get_mut("position")
+            .and_then(Item::as_table_like_mut),
+        "mode",
+    );
+    for strategy in strategies(document) {
+        normalize(Some(&mut *strategy), "grid_type");
+        normalize(Some(strategy), "position_mode");
+    }
+}
+
+/// Version 2 strategies set flat fields such as `levels` and
+/// `position_mode`; move them into the sections the main config uses
+fn nest_strategy_fields(document: &mut Table) {
+    const MOVES: [(&str, &str, &str); 6] = [
+        ("initial_price", "base", "initial_price"),
+        ("grid_type", "base", "grid_type"),
+        ("levels", "grid", "levels"),
+        ("position_mode", "position", "mode"),
+        ("base_size", "position", "base_size"),
+        ("multiplier", "position", "multiplier"),
+    ];
+
+    for strategy in strategies(document) {
+        for (from, section, to) in MOVES {
+            let Some(Item::Value(mut value)) = strategy.remove(from) else {
+                continue;
+            };
+            // Comments after the old key cannot follow it into an inline table
+            value.decor_mut().clear();
+            let section = strategy
+                .entry(section)
+                .or_insert(Item::Value(Value::InlineTable(InlineTable::new())));
+            if let Some(table) = section.as_table_like_mut() {
+                table.insert(to, Item::Value(value));
+            }
+            if let Some(inline) = section.as_inline_table_mut() {
+                inline.fmt();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GridType, PositionMode};
+
+    const VERSION_1: &str = r#"
+[base]
+initial_price = 100.0
+grid_type = "Fixed"
+
+[grid]
+levels = [1.0, 2.0]
+
+[position]
+mode = "current_multiple"
+base_size = 100.0
+multiplier = 2.0
+
+[[strategies]]
+name = "legacy"
+initial_price = 50.0
+grid_type = "AVERAGE"
+levels = [1.0]
+position_mode = "Increment Multiple"
+base_size = 10.0
+multiplier = 1.5
+"#;
+
+    #[test]
+    fn test_upgrades_version_1() {
+        let config: Config = parse(VERSION_1, Format::Toml).unwrap();
+        assert_eq!(config.version, CURRENT_VERSION);
+        assert_eq!(config.base.grid_type, GridType::Fixed);
+        assert_eq!(config.position.mode, PositionMode::CurrentMultiple);
+
+        let strategy = config.strategy("legacy").unwrap();
+        assert_eq!(strategy.base.grid_type, Some(GridType::Average));
+        assert_eq!(strategy.grid.levels, Some(vec![1.0]));
+        assert_eq!(
+            strategy.position.mode,
+            Some(PositionMode::IncrementMultiple)
+        );
+        assert_eq!(strategy.position.multiplier, Some(1.5));
+        let resolved = strategy.to_config(&config).unwrap();
+        assert_eq!(resolved.base.initial_price, 50.0);
+    }
+
+    #[test]
+    fn test_version_checks() {
+        let mut current: DocumentMut = "version = 3".parse().unwrap();
+        assert!(migrate(&mut current).unwrap().is_empty());
+
+        let mut newer: DocumentMut = "version = 99".parse().unwrap();
+        let err = migrate(&mut newer).unwrap_err();
+        assert!(
+            err.to_string().contains("newer than the supported version"),
+            "{}",
+            err
+        );
+
+        let invalid: DocumentMut = "version = \"two\"".parse().unwrap();
+        assert!(version_of(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_null_values() {
+        let json = r#"{
+  "base": { "initial_price": 100.0, "grid_type": "fixed" },
+  "grid": { "levels": [1.0] },
+  "position": { "mode": "fixed", "base_size": 10.0, "multiplier": null }
+}"#;
+        let yaml = "base:\n  initial_price: 100.0\n  grid_type: Fixed\ngrid:\n  levels: [1.0]\nposition:\n  mode: fixed\n  base_size: 10.0\n  multiplier: ~\n";
+        let current = json.replacen('{', "{\n  \"version\": 3,", 1);
+
+        for (contents, format) in [
+            (json, Format::Json),
+            (&current, Format::Json),
+            (yaml, Format::Yaml),
+        ] {
+            let config: Config = parse(contents, format).unwrap();
+            assert_eq!(config.version, CURRENT_VERSION);
+            assert_eq!(config.position.multiplier, None);
+        }
+    }
+
+    #[test]
+    fn test_migrate_file_keeps_comments() {
+        let dir = std::env::temp_dir().join(format!("grid-migrate-toml-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        let toml = format!("# Main ladder\n{}", VERSION_1.trim_start());
+        let toml = toml.replace(
+            "mode = \"current_multiple\"",
+            "mode = \"current_multiple\"  # doubles each fill",
+        );
+        fs::write(&path, &toml).unwrap();
+
+        migrate_file(&path, Format::Toml).unwrap().unwrap();
+        let migrated = fs::read_to_string(&path).unwrap();
+        assert!(migrated.contains("# Main ladder\n"), "{}", migrated);
+        assert!(
+            migrated.contains("mode = \"current-multiple\"  # doubles each fill"),
+            "{}",
+            migrated
+        );
+        assert!(
+            migrated.contains("grid = { levels = [1.0] }"),
+            "{}",
+            migrated
+        );
+
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.version, CURRENT_VERSION);
+        assert_eq!(
+            config.strategy("legacy").unwrap().position.multiplier,
+            Some(1.5)
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_file_keeps_backup() {
+        let dir = std::env::temp_dir().join(format!("grid-migrate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.yaml");
+        let yaml = "base:\n  initial_price: 100.0\n  grid_type: Average\ngrid:\n  levels: [1.0]\nposition:\n  mode: fixed\n  base_size: 10.0\n";
+        fs::write(&path, yaml).unwrap();
+
+        let migrated = migrate_file(&path, Format::Yaml).unwrap().unwrap();
+        assert_eq!(migrated.from, 1);
+        assert_eq!(migrated.steps.len(), 2);
+        assert_eq!(fs::read_to_string(&migrated.backup).unwrap(), yaml);
+
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.version, CURRENT_VERSION);
+        assert_eq!(config.base.grid_type, GridType::Average);
+        assert!(fs::read_to_string(&path).unwrap().contains("version: 3"));
+
+        // Running again has nothing to do
+        assert!(migrate_file(&path, Format::Yaml).unwrap().is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
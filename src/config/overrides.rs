@@ -0,0 +1,218 @@
+use super::Config;
+use crate::error::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefix of environment variables that override config values
+pub const ENV_PREFIX: &str = "GRID_";
+
+/// Separator between table and key in override environment variables
+pub const ENV_SEPARATOR: &str = "__";
+
+/// A single config value set from outside the file, e.g.
+/// `base.initial_price=123`. The value is read as a TOML value and falls
+/// back to a plain string, so `grid_type=average` needs no quotes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Override {
+    /// Dotted path of the value, e.g. `position.base_size`
+    pub path: String,
+    pub value: String,
+}
+
+impl Override {
+    pub fn new(path: impl Into<String>, value: impl Into<String>) -> Self {
+        Override {
+            path: path.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Overrides from `GRID_<TABLE>__<KEY>` environment variables, e.g.
+    /// `GRID_BASE__INITIAL_PRICE`. Variables without the separator are not
+    /// overrides and are skipped.
+    pub fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> Vec<Override> {
+        let mut overrides: Vec<Override> = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let rest = name.strip_prefix(ENV_PREFIX)?;
+                rest.contains(ENV_SEPARATOR).then(|| {
+                    let path = rest
+                        .to_lowercase()
+                        .split(ENV_SEPARATOR)
+                        .collect::<Vec<_>>()
+                        .join(".");
+                    Override::new(path, value)
+                })
+            })
+            .collect();
+        // Environment order is unspecified; keep the result stable
+        overrides.sort_by(|a, b| a.path.cmp(&b.path));
+        overrides
+    }
+
+    /// Overrides from the process environment
+    pub fn from_env() -> Vec<Override> {
+        Override::from_vars(std::env::vars())
+    }
+
+    fn parsed_value(&self) -> toml::Value {
+        toml::from_str::<toml::Table>(&format!("value = {}", self.value))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(self.value.clone()))
+    }
+}
+
+impl FromStr for Override {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('=') {
+            Some((path, value)) if !path.trim().is_empty() => {
+                Ok(Override::new(path.trim(), value.trim()))
+            }
+            _ => Err(Error::Parse {
+                path: None,
+                message: format!("Invalid override '{}', expected PATH=VALUE", s),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Override {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.path, self.value)
+    }
+}
+
+impl Config {
+    /// Apply overrides in order, later ones winning, and validate the result
+    pub fn with_overrides(&self, overrides: &[Override]) -> Result<Config> {
+        if overrides.is_empty() {
+            return Ok(self.clone());
+        }
+
+        let serialize = |config: &Config| {
+            toml::Value::try_from(config).map_err(|e| Error::Serialize {
+                message: e.to_string(),
+            })
+        };
+
+        let mut document = serialize(self)?;
+        for o in overrides {
+            set(&mut document, o)?;
+        }
+
+        let config: Config = document
+            .try_into()
+            .map_err(|e: toml::de::Error| Error::Parse {
+                path: None,
+                message: format!("Invalid override: {}", e.message()),
+            })?;
+
+        // Keys the config does not know are dropped when deserializing
+        let applied = serialize(&config)?;
+        if let Some(o) = overrides
+            .iter()
+            .find(|o| lookup(&applied, &o.path).is_none())
+        {
+            return Err(Error::invalid_field(
+                &o.path,
+                format!("Unknown config field in override '{}'", o),
+            ));
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+fn set(document: &mut toml::Value, o: &Override) -> Result<()> {
+    let keys: Vec<&str> = o.path.split('.').collect();
+    let (last, tables) = keys.split_last().expect("split always yields a segment");
+
+    let mut table = document
+        .as_table_mut()
+        .expect("configs serialize to a table");
+    for key in tables {
+        table = table
+            .entry(key.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| Error::invalid_field(&o.path, format!("'{}' is not a table", key)))?;
+    }
+    table.insert(last.to_string(), o.parsed_value());
+    Ok(())
+}
+
+fn lookup<'a>(document: &'a toml::Value, path: &str) -> Option<&'a toml::Value> {
+    path.split('.')
+        .try_fold(document, |value, key| value.get(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GridType;
+
+    fn config() -> Config {
+        toml::from_str(include_str!("../../simple_config.toml")).unwrap()
+    }
+
+    #[test]
+    fn test_env_and_cli_layers() {
+        let env = Override::from_vars([
+            ("GRID_BASE__INITIAL_PRICE".to_string(), "250".to_string()),
+            ("GRID_POSITION__BASE_SIZE".to_string(), "10.5".to_string()),
+            ("GRID_UNRELATED".to_string(), "1".to_string()),
+            ("PATH".to_string(), "/bin".to_string()),
+        ]);
+        assert_eq!(
+            env,
+            vec![
+                Override::new("base.initial_price", "250"),
+                Override::new("position.base_size", "10.5"),
+            ]
+        );
+
+        let cli: Override = "base.initial_price=123".parse().unwrap();
+        let grid_type: Override = "base.grid_type = average".parse().unwrap();
+        let overrides: Vec<_> = env.into_iter().chain([cli, grid_type]).collect();
+
+        let cfg = config().with_overrides(&overrides).unwrap();
+        assert_eq!(cfg.base.initial_price, 123.0);
+        assert_eq!(cfg.base.grid_type, GridType::Average);
+        assert_eq!(cfg.position.base_size, 10.5);
+    }
+
+    #[test]
+    fn test_lists_and_new_keys() {
+        let cfg = config()
+            .with_overrides(&[
+                Override::new("grid.levels", "[2, 4.5]"),
+                Override::new("position.mode", "current-multiple"),
+                Override::new("position.multiplier", "1.5"),
+            ])
+            .unwrap();
+        assert_eq!(cfg.grid.levels, vec![2.0, 4.5]);
+        assert_eq!(cfg.position.multiplier, Some(1.5));
+    }
+
+    #[test]
+    fn test_rejects_bad_overrides() {
+        assert!("base.initial_price".parse::<Override>().is_err());
+
+        let typo = config().with_overrides(&[Override::new("base.inital_price", "1")]);
+        assert!(
+            matches!(typo, Err(Error::InvalidField { field, .. }) if field == "base.inital_price")
+        );
+
+        let wrong_type = config().with_overrides(&[Override::new("base.initial_price", "cheap")]);
+        assert!(matches!(wrong_type, Err(Error::Parse { .. })));
+
+        // The result is validated like a freshly loaded file
+        let invalid =
+            config().with_overrides(&[Override::new("position.mode", "current-multiple")]);
+        assert!(matches!(invalid, Err(Error::Constraint { .. })));
+    }
+}
@@ -0,0 +1,317 @@
+use super::{Config, Strategy};
+use crate::error::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Name a strategy's `extends` uses to inherit from the main configuration
+pub const MAIN_CONFIG: &str = "main";
+
+/// Sections a strategy shares with the main configuration
+pub const SECTIONS: [&str; 3] = ["base", "grid", "position"];
+
+/// Where a resolved strategy value was defined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Main,
+    Strategy(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Main => f.write_str("main config"),
+            Source::Strategy(name) => write!(f, "strategy '{}'", name),
+        }
+    }
+}
+
+/// A strategy with its `extends` chain merged into a complete config
+#[derive(Debug, Clone)]
+pub struct ResolvedStrategy {
+    pub name: String,
+    pub config: Config,
+    /// Where each value came from, keyed by its path in `config`, e.g.
+    /// `position.mode`. Optional fields that no layer sets have no entry.
+    pub sources: BTreeMap<String, Source>,
+}
+
+impl ResolvedStrategy {
+    /// Where the value at `path` came from. `path` is a path into `config`
+    /// and may point inside a field, e.g. `grid.levels[2]`.
+    pub fn source_of(&self, path: &str) -> Option<&Source> {
+        self.sources.iter().find_map(|(field, source)| {
+            let rest = path.strip_prefix(field.as_str())?;
+            (rest.is_empty() || rest.starts_with('[') || rest.starts_with('.')).then_some(source)
+        })
+    }
+
+    /// Map a path in the merged config to the same value in this strategy's
+    /// `[[strategies]]` entry at `index`. Values inherited from the main
+    /// config or another strategy return `None`, since they are reported
+    /// where they are defined.
+    pub fn own_path(&self, index: usize, path: &str) -> Option<String> {
+        match self.source_of(path) {
+            Some(Source::Strategy(name)) if *name == self.name => {}
+            None => {}
+            Some(_) => return None,
+        }
+        Some(format!("strategies[{}].{}", index, path))
+    }
+
+    /// Path of the value at `path` in the layer that defines it: the main
+    /// config's own path, or the `[[strategies]]` entry it was inherited
+    /// from. Values no layer sets return `None`.
+    pub fn defined_at(&self, config: &Config, path: &str) -> Option<String> {
+        match self.source_of(path)? {
+            Source::Main => Some(path.to_string()),
+            Source::Strategy(name) => {
+                let index = config
+                    .strategies
+                    .iter()
+                    .flatten()
+                    .position(|s| s.name == *name)?;
+                Some(format!("strategies[{}].{}", index, path))
+            }
+        }
+    }
+}
+
+impl Strategy {
+    /// Follow `extends` and merge every layer into a complete config. Fields
+    /// set closer to this strategy win over inherited ones.
+    pub fn resolve(&self, config: &Config) -> Result<ResolvedStrategy> {
+        let (chain, inherits_main) = self.chain(config)?;
+
+        let mut merged = toml::Table::new();
+        let mut sources = BTreeMap::new();
+        if inherits_main {
+            overlay(&mut merged, &mut sources, config, Source::Main)?;
+        }
+        for strategy in chain.iter().rev() {
+            let source = Source::Strategy(strategy.name.clone());
+            overlay(&mut merged, &mut sources, strategy, source)?;
+        }
+
+        Ok(ResolvedStrategy {
+            name: self.name.clone(),
+            config: Config {
+                version: config.version,
+                include: None,
+                base: self.section(&merged, "base")?,
+                grid: self.section(&merged, "grid")?,
+                position: self.section(&merged, "position")?,
+                strategies: None,
+                lints: config.lints.clone(),
+                // Alerts and assets belong to the main config, which reports
+                // their errors once
+                alerts: None,
+                portfolio: None,
+            },
+            sources,
+        })
+    }
+
+    /// The fully merged config for this strategy
+    pub fn to_config(&self, config: &Config) -> Result<Config> {
+        self.resolve(config).map(|resolved| resolved.config)
+    }
+
+    /// This strategy followed by every strategy it extends, and whether the
+    /// chain ends at the main config
+    fn chain<'a>(&'a self, config: &'a Config) -> Result<(Vec<&'a Strategy>, bool)> {
+        let mut chain = vec![self];
+
+        while let Some(parent) = &chain[chain.len() - 1].extends {
+            if parent == MAIN_CONFIG {
+                return Ok((chain, true));
+            }
+
+            let next = config
+                .strategies
+                .iter()
+                .flatten()
+                .find(|s| &s.name == parent)
+                .ok_or_else(|| {
+                    Error::invalid_field(
+                        "extends",
+                        format!("Unknown strategy '{}' in extends", parent),
+                    )
+                    .in_strategy(&self.name)
+                })?;
+
+            if chain.iter().any(|s| s.name == next.name) {
+                let mut names: Vec<&str> = chain.iter().map(|s| s.name.as_str()).collect();
+                names.push(&next.name);
+                return Err(Error::constraint(
+                    "extends",
+                    format!("Strategy inheritance cycle: {}", names.join(" -> ")),
+                )
+                .in_strategy(&self.name));
+            }
+            chain.push(next);
+        }
+        Ok((chain, false))
+    }
+
+    fn section<T: DeserializeOwned>(&self, merged: &toml::Table, name: &str) -> Result<T> {
+        let table = merged.get(name).cloned();
+        table
+            .unwrap_or_else(|| toml::Value::Table(toml::Table::new()))
+            .try_into()
+            .map_err(|e: toml::de::Error| {
+                Error::invalid_field(
+                    name,
+                    format!(
+                        "Incomplete {} section ({}); set the field or extend a strategy that does",
+                        name,
+                        e.message()
+                    ),
+                )
+                .in_strategy(&self.name)
+            })
+    }
+}
+
+/// Copy every field `layer` sets in the shared sections over `merged`,
+/// recording `source` as their origin
+fn overlay(
+    merged: &mut toml::Table,
+    sources: &mut BTreeMap<String, Source>,
+    layer: &impl Serialize,
+    source: Source,
+) -> Result<()> {
+    let layer = toml::Table::try_from(layer).map_err(|e| Error::Serialize {
+        message: e.to_string(),
+    })?;
+
+    for section in SECTIONS {
+        let Some(toml::Value::Table(fields)) = layer.get(section) else {
+            continue;
+        };
+        let target = merged
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        for (key, value) in fields {
+            if let toml::Value::Table(target) = target {
+                target.insert(key.clone(), value.clone());
+            }
+            sources.insert(format!("{}.{}", section, key), source.clone());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"
+[base]
+initial_price = 100.0
+grid_type = "fixed"
+
+[grid]
+levels = [1.0, 2.0, 3.0]
+
+[position]
+mode = "current-multiple"
+base_size = 100.0
+multiplier = 2.0
+
+[[strategies]]
+name = "base"
+extends = "main"
+base.grid_type = "average"
+
+[[strategies]]
+name = "wide"
+extends = "base"
+grid.levels = [5.0, 10.0]
+
+[[strategies]]
+name = "standalone"
+base = { initial_price = 50.0, grid_type = "fixed" }
+grid = { levels = [1.0] }
+position = { mode = "fixed", base_size = 10.0 }
+
+[[strategies]]
+name = "partial"
+grid.levels = [1.0]
+
+[[strategies]]
+name = "loop-a"
+extends = "loop-b"
+
+[[strategies]]
+name = "loop-b"
+extends = "loop-a"
+"#;
+
+    fn config() -> Config {
+        toml::from_str(SOURCE).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_chain() {
+        let cfg = config();
+        let resolved = cfg.resolve_strategy("wide").unwrap();
+
+        assert_eq!(resolved.config.base.initial_price, 100.0);
+        assert_eq!(resolved.config.base.grid_type, crate::GridType::Average);
+        assert_eq!(resolved.config.grid.levels, vec![5.0, 10.0]);
+        assert_eq!(resolved.config.position.multiplier, Some(2.0));
+
+        assert_eq!(
+            resolved.source_of("base.initial_price"),
+            Some(&Source::Main)
+        );
+        assert_eq!(
+            resolved.source_of("base.grid_type"),
+            Some(&Source::Strategy("base".to_string()))
+        );
+        assert_eq!(
+            resolved.source_of("grid.levels[1]"),
+            Some(&Source::Strategy("wide".to_string()))
+        );
+
+        assert_eq!(
+            resolved.own_path(1, "grid.levels[1]").as_deref(),
+            Some("strategies[1].grid.levels[1]")
+        );
+        assert_eq!(resolved.own_path(1, "base.grid_type"), None);
+    }
+
+    #[test]
+    fn test_standalone_and_missing_fields() {
+        let cfg = config();
+        let standalone = cfg.resolve_strategy("standalone").unwrap();
+        assert_eq!(standalone.config.base.initial_price, 50.0);
+        assert!(!standalone.sources.contains_key("position.multiplier"));
+
+        match cfg.resolve_strategy("partial") {
+            Err(Error::InvalidField {
+                field,
+                strategy,
+                message,
+            }) => {
+                assert_eq!(field, "base");
+                assert_eq!(strategy.as_deref(), Some("partial"));
+                assert!(message.contains("initial_price"), "{}", message);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let err = config().resolve_strategy("loop-a").unwrap_err();
+        assert!(matches!(err, Error::Constraint { .. }));
+        assert!(
+            err.to_string().contains("loop-a -> loop-b -> loop-a"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use super::lint::{Lint, LintLevel};
+use super::Config;
+use crate::grid_calculator::PositionMode;
+use clap::ValueEnum;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{
+    ArrayValidation, InstanceType, NumberValidation, ObjectValidation, Schema, SchemaObject,
+    SingleOrVec,
+};
+use serde_json::{json, Value};
+
+/// JSON Schema for `Config` and everything it contains. Value ranges and
+/// the multiplier rule match what `Config::validate` enforces.
+pub fn json_schema() -> Value {
+    let root = schemars::schema_for!(Config);
+    let mut schema = serde_json::to_value(root).expect("schemas serialize to JSON");
+
+    let multiple_modes: Vec<&str> = PositionMode::value_variants()
+        .iter()
+        .filter(|mode| mode.uses_multiplier())
+        .map(|mode| mode.as_str())
+        .collect();
+    if let Some(position) = schema.pointer_mut("/definitions/PositionConfig") {
+        position["if"] = json!({
+            "properties": { "mode": { "enum": multiple_modes } },
+            "required": ["mode"],
+        });
+        position["then"] = json!({ "required": ["multiplier"] });
+    }
+    schema
+}
+
+/// Schema for the `[lints]` table: lint names mapped to levels
+pub(crate) fn lints(gen: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        object: Some(Box::new(ObjectValidation {
+            property_names: Some(Box::new(gen.subschema_for::<Lint>())),
+            additional_properties: Some(Box::new(gen.subschema_for::<LintLevel>())),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Schema for a list of grid levels: at least one percentage strictly
+/// between 0 and 100
+pub(crate) fn levels(_: &mut SchemaGenerator) -> Schema {
+    let level = SchemaObject {
+        instance_type: Some(InstanceType::Number.into()),
+        number: Some(Box::new(NumberValidation {
+            exclusive_minimum: Some(0.0),
+            exclusive_maximum: Some(100.0),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+    SchemaObject {
+        instance_type: Some(InstanceType::Array.into()),
+        array: Some(Box::new(ArrayValidation {
+            items: Some(SingleOrVec::Single(Box::new(level.into()))),
+            min_items: Some(1),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Schema for a position multiplier: a number greater than 0
+pub(crate) fn multiplier(_: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::Number.into()),
+        number: Some(Box::new(NumberValidation {
+            exclusive_minimum: Some(0.0),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enum_values(schema: &Value, definition: &str) -> Vec<String> {
+        schema["definitions"][definition]["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|variant| variant["enum"].as_array().unwrap())
+            .map(|value| value.as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_enum_values() {
+        let schema = json_schema();
+        assert_eq!(enum_values(&schema, "GridType"), vec!["fixed", "average"]);
+        assert_eq!(
+            enum_values(&schema, "PositionMode"),
+            vec!["fixed", "current-multiple", "increment-multiple"]
+        );
+        assert_eq!(
+            enum_values(&schema, "LintLevel"),
+            vec!["allow", "warn", "deny"]
+        );
+        assert!(enum_values(&schema, "Lint").contains(&"aggressive-multiplier".to_string()));
+
+        let lints = &schema["properties"]["lints"];
+        assert_eq!(lints["propertyNames"]["$ref"], "#/definitions/Lint");
+        assert_eq!(
+            lints["additionalProperties"]["$ref"],
+            "#/definitions/LintLevel"
+        );
+    }
+
+    #[test]
+    fn test_ranges_and_rules_match_validation() {
+        let schema = json_schema();
+        for levels in [
+            &schema["definitions"]["GridConfig"]["properties"]["levels"],
+            &schema["definitions"]["PartialGrid"]["properties"]["levels"],
+        ] {
+            assert_eq!(levels["minItems"], 1);
+            assert_eq!(levels["items"]["exclusiveMinimum"], 0.0);
+            assert_eq!(levels["items"]["exclusiveMaximum"], 100.0);
+        }
+
+        for position in ["PositionConfig", "PartialPosition"] {
+            let multiplier = &schema["definitions"][position]["properties"]["multiplier"];
+            assert_eq!(multiplier["exclusiveMinimum"], 0.0);
+        }
+
+        let position = &schema["definitions"]["PositionConfig"];
+        assert_eq!(
+            position["if"]["properties"]["mode"]["enum"],
+            json!(["current-multiple", "increment-multiple"])
+        );
+        assert_eq!(position["then"]["required"], json!(["multiplier"]));
+        assert_eq!(
+            schema["definitions"]["Strategy"]["required"],
+            json!(["name"])
+        );
+    }
+
+    #[test]
+    fn test_descriptions_and_fields() {
+        let schema = json_schema();
+        let base = &schema["definitions"]["BaseConfig"]["properties"]["initial_price"];
+        assert_eq!(base["description"], "Price the grid starts from");
+
+        // Every key a serialized config uses is described by the schema
+        let config: Config = toml::from_str(include_str!("../../config_example.toml")).unwrap();
+        let document = serde_json::to_value(&config).unwrap();
+        for (key, value) in document.as_object().unwrap() {
+            assert!(schema["properties"].get(key).is_some(), "{}", key);
+            if let Some(table) = value.as_object() {
+                let definition = schema["properties"][key]["allOf"][0]["$ref"]
+                    .as_str()
+                    .or(schema["properties"][key]["$ref"].as_str())
+                    .unwrap()
+                    .trim_start_matches("#/definitions/");
+                for field in table.keys() {
+                    let properties = &schema["definitions"][definition]["properties"];
+                    assert!(properties.get(field).is_some(), "{}.{}", key, field);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use crate::config::format::Format;
+use crate::config::{migrate, Config};
+use crate::error::Error;
+use serde::Serialize;
+use std::fmt;
+use std::ops::Range;
+use toml_edit::{DocumentMut, ImDocument, Item};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// Location of a diagnostic in the source file. Lines and columns start at 1.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    fn new(source: &str, range: Range<usize>) -> Self {
+        let before = &source[..range.start.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Span {
+            start: range.start,
+            end: range.end,
+            line,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Path of the offending field, e.g. `strategies[1].grid.levels[2]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    pub fn error(path: Option<String>, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            path,
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub fn warning(path: Option<String>, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            path,
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    /// Turn a validation error into a diagnostic. `prefix` is the path of
+    /// the table the error's field is relative to, e.g. `strategies[0]`.
+    pub fn from_error(error: &Error, prefix: Option<&str>) -> Self {
+        let path = error.field().map(|field| match prefix {
+            Some(prefix) => format!("{}.{}", prefix, field),
+            None => field.to_string(),
+        });
+        Diagnostic::error(path, error.to_string())
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(span) = &self.span {
+            write!(f, "{}:{}: ", span.line, span.column)?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Whether any diagnostic is an error rather than a warning
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+/// Parse and validate a TOML config, reporting every problem found along
+/// with its location in `source`
+pub fn check_toml(source: &str) -> Vec<Diagnostic> {
+    let outdated = source
+        .parse::<DocumentMut>()
+        .ok()
+        .and_then(|document| migrate::version_of(&document).ok())
+        .filter(|version| *version < migrate::CURRENT_VERSION);
+
+    let mut diagnostics = match outdated {
+        // Older files are checked as they will be read, after upgrading
+        Some(version) => {
+            let mut diagnostics = vec![Diagnostic::warning(
+                Some("version".to_string()),
+                format!(
+                    "Config version {} is older than the current version {}; run `migrate` to upgrade the file",
+                    version,
+                    migrate::CURRENT_VERSION
+                ),
+            )];
+            match migrate::parse::<Config>(source, Format::Toml) {
+                Ok(config) => diagnostics.extend(config.diagnostics()),
+                Err(e) => diagnostics.push(Diagnostic::error(None, e.to_string())),
+            }
+            diagnostics
+        }
+        None => match toml::from_str::<Config>(source) {
+            Ok(config) => config.diagnostics(),
+            Err(e) => {
+                let mut diagnostic = Diagnostic::error(None, e.message().trim());
+                diagnostic.span = e.span().map(|range| Span::new(source, range));
+                vec![diagnostic]
+            }
+        },
+    };
+    locate(&mut diagnostics, source);
+    diagnostics
+}
+
+/// Fill in the span of every diagnostic that has a path but no span yet.
+/// Fields that are missing from the file point at their closest parent.
+pub fn locate(diagnostics: &mut [Diagnostic], source: &str) {
+    let Ok(document) = ImDocument::parse(source) else {
+        return;
+    };
+
+    for diagnostic in diagnostics.iter_mut().filter(|d| d.span.is_none()) {
+        if let Some(path) = &diagnostic.path {
+            diagnostic.span = find_span(document.as_item(), path).map(|r| Span::new(source, r));
+        }
+    }
+}
+
+fn find_span(root: &Item, path: &str) -> Option<Range<usize>> {
+    let mut item = root;
+    let mut span = None;
+
+    for segment in path.split('.') {
+        let (key, indices) = match segment.find('[') {
+            Some(i) => (&segment[..i], &segment[i..]),
+            None => (segment, ""),
+        };
+
+        item = match item.get(key) {
+            Some(next) => next,
+            None => return span,
+        };
+        span = item.span().or(span);
+
+        for index in indices.split(']').filter_map(|s| s.strip_prefix('[')) {
+            let Ok(index) = index.parse::<usize>() else {
+                return span;
+            };
+            item = match item.get(index) {
+                Some(next) => next,
+                None => return span,
+            };
+            span = item.span().or(span);
+        }
+    }
+
+    span
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"version = 3
+[base]
+initial_price = 100.0
+grid_type = "fixed"
+
+[grid]
+levels = [1.0, 120.0, 3.0]
+
+[position]
+mode = "current-multiple"
+base_size = 100.0
+
+[[strategies]]
+name = "wide"
+base.initial_price = 100.0
+base.grid_type = "fixed"
+grid.levels = [0.0, 2.0]
+position.mode = "fixed"
+position.base_size = 100.0
+
+[[strategies]]
+name = "wide"
+base.initial_price = 100.0
+base.grid_type = "average"
+grid.levels = []
+position.mode = "increment-multiple"
+position.base_size = 100.0
+"#;
+
+    #[test]
+    fn test_collects_all_errors() {
+        let diagnostics = check_toml(SOURCE);
+        let paths: Vec<_> = diagnostics
+            .iter()
+            .map(|d| d.path.as_deref().unwrap_or_default())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "position.multiplier",
+                "grid.levels[1]",
+                "strategies[0].grid.levels[0]",
+                "strategies[1].position.multiplier",
+                "strategies[1].grid.levels",
+                "strategies[1].name",
+                "grid.levels[2]",
+            ]
+        );
+        assert!(has_errors(&diagnostics));
+        assert_eq!(diagnostics[6].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_spans_point_into_source() {
+        let diagnostics = check_toml(SOURCE);
+        let level = &diagnostics[1];
+        let span = level.span.as_ref().unwrap();
+        assert_eq!((span.line, span.column), (7, 16));
+        assert_eq!(&SOURCE[span.start..span.end], "120.0");
+
+        // Missing fields point at the enclosing table
+        let multiplier = diagnostics[0].span.as_ref().unwrap();
+        assert_eq!(multiplier.line, 9);
+
+        let nested = diagnostics[2].span.as_ref().unwrap();
+        assert_eq!((nested.line, nested.column), (17, 16));
+    }
+
+    #[test]
+    fn test_outdated_versions() {
+        let source = SOURCE
+            .replace("version = 3\n", "")
+            .replace("\"fixed\"\n\n[grid]", "\"Fixed\"\n\n[grid]");
+        let diagnostics = check_toml(&source);
+        assert_eq!(diagnostics[0].path.as_deref(), Some("version"));
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics.len(), 8);
+
+        let newer = SOURCE.replace("version = 3", "version = 99");
+        let diagnostics = check_toml(&newer);
+        assert!(diagnostics[0]
+            .message
+            .contains("newer than the supported version"));
+        assert_eq!(diagnostics[0].span.as_ref().unwrap().line, 1);
+    }
+
+    #[test]
+    fn test_parse_errors_have_spans() {
+        let source = SOURCE.replace("\"fixed\"\n\n[grid]", "\"sideways\"\n\n[grid]");
+        let diagnostics = check_toml(&source);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0]
+            .message
+            .contains("unknown variant `sideways`"));
+        assert_eq!(diagnostics[0].span.as_ref().unwrap().line, 4);
+        assert_eq!(diagnostics[0].to_string().split(':').next(), Some("4"));
+    }
+}
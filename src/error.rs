@@ -0,0 +1,185 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file failed
+    Io { path: PathBuf, source: io::Error },
+    /// A config file is not valid TOML or does not match the config shape
+    Parse {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    /// A config could not be serialized
+    Serialize { message: String },
+    /// A single field holds a value outside its allowed range
+    InvalidField {
+        field: String,
+        strategy: Option<String>,
+        message: String,
+    },
+    /// A rule spanning several fields is violated; `field` is the one to fix
+    Constraint {
+        field: String,
+        strategy: Option<String>,
+        message: String,
+    },
+    /// A named strategy does not exist in the config
+    StrategyNotFound { name: String },
+    /// A fill cannot be applied to the tracked grid
+    InvalidFill { message: String },
+    /// Orders cannot be built from a ladder with the given options
+    InvalidOrder { message: String },
+    /// A server could not listen on its address
+    Bind { address: String, message: String },
+    /// An alert could not be delivered to a sink
+    Alert { sink: String, message: String },
+}
+
+impl Error {
+    pub(crate) fn invalid_field(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Error::InvalidField {
+            field: field.into(),
+            strategy: None,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn constraint(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Error::Constraint {
+            field: field.into(),
+            strategy: None,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn parse(message: impl Into<String>) -> Self {
+        Error::Parse {
+            path: None,
+            message: message.into(),
+        }
+    }
+
+    /// Prefix a parse error with where in its input it occurred, e.g.
+    /// `line 3`
+    pub(crate) fn at(self, location: impl fmt::Display) -> Self {
+        match self {
+            Error::Parse { path, message } => Error::Parse {
+                path,
+                message: format!("{}: {}", location, message),
+            },
+            other => other,
+        }
+    }
+
+    /// Attribute a parse error to the file it was read from
+    pub fn in_file(self, path: &Path) -> Self {
+        match self {
+            Error::Parse {
+                path: None,
+                message,
+            } => Error::Parse {
+                path: Some(path.to_path_buf()),
+                message,
+            },
+            other => other,
+        }
+    }
+
+    /// Attribute a validation error to the strategy `name`
+    pub fn in_strategy(self, name: &str) -> Self {
+        match self {
+            Error::InvalidField { field, message, .. } => Error::InvalidField {
+                field,
+                strategy: Some(name.to_string()),
+                message,
+            },
+            Error::Constraint { field, message, .. } => Error::Constraint {
+                field,
+                strategy: Some(name.to_string()),
+                message,
+            },
+            other => other,
+        }
+    }
+
+    /// Path of the config field a validation error refers to
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            Error::InvalidField { field, .. } | Error::Constraint { field, .. } => Some(field),
+            _ => None,
+        }
+    }
+
+    /// Name of the strategy the error belongs to, if any
+    pub fn strategy(&self) -> Option<&str> {
+        match self {
+            Error::InvalidField { strategy, .. } | Error::Constraint { strategy, .. } => {
+                strategy.as_deref()
+            }
+            Error::StrategyNotFound { name } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Parse {
+                path: Some(path),
+                message,
+            } => write!(f, "{}: {}", path.display(), message),
+            Error::Parse {
+                path: None,
+                message,
+            } => f.write_str(message),
+            Error::Serialize { message } => write!(f, "Failed to serialize config: {}", message),
+            Error::InvalidField {
+                field,
+                strategy: Some(name),
+                message,
+            } => write!(f, "{} (strategy '{}', field {})", message, name, field),
+            Error::InvalidField {
+                field,
+                strategy: None,
+                message,
+            } => write!(f, "{} (field {})", message, field),
+            Error::Constraint {
+                strategy: Some(name),
+                message,
+                ..
+            } => write!(f, "{} (strategy '{}')", message, name),
+            Error::Constraint {
+                strategy: None,
+                message,
+                ..
+            } => f.write_str(message),
+            Error::StrategyNotFound { name } => {
+                write!(f, "Strategy '{}' not found in config", name)
+            }
+            Error::InvalidFill { message } | Error::InvalidOrder { message } => {
+                f.write_str(message)
+            }
+            Error::Bind { address, message } => {
+                write!(f, "Cannot listen on {}: {}", address, message)
+            }
+            Error::Alert { sink, message } => {
+                write!(f, "Alert sink {} failed: {}", sink, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
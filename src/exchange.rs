@@ -0,0 +1,205 @@
+use crate::error::{Error, Result};
+use crate::feed::PriceTick;
+use crate::grid_calculator::Execution;
+use crate::orders::{build_orders, Order, OrderOptions};
+use crate::state::{GridState, Pnl};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// An in-process exchange for paper trading a grid. It rests the grid's
+/// buy limit orders and fills them as market prices arrive, recording each
+/// fill in the grid state so later orders are sized from what filled.
+#[derive(Debug, Clone)]
+pub struct MockExchange {
+    state: GridState,
+    options: OrderOptions,
+    /// Fee charged on each fill, as a fraction of its value
+    fee_rate: f64,
+    book: Vec<Order>,
+    fills: Vec<ExchangeFill>,
+    last_price: Option<f64>,
+}
+
+/// A resting order that filled
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExchangeFill {
+    #[serde(flatten)]
+    pub order: Order,
+    pub timestamp: DateTime<Utc>,
+    /// Market price that crossed the order; the fill is at the order's price
+    pub market_price: f64,
+    pub fee: f64,
+}
+
+impl MockExchange {
+    /// Open an exchange with an order resting for every level of `state`
+    /// still to fill
+    pub fn new(state: GridState, options: OrderOptions, fee_rate: f64) -> Result<Self> {
+        if !(0.0..1.0).contains(&fee_rate) {
+            return Err(Error::InvalidOrder {
+                message: format!("Fee rate must be between 0 and 1, got {}", fee_rate),
+            });
+        }
+        let book = build_orders(&state.remaining(), &options)?;
+        Ok(Self {
+            state,
+            options,
+            fee_rate,
+            book,
+            fills: Vec::new(),
+            last_price: None,
+        })
+    }
+
+    /// Match resting orders against a market price. Orders at or above the
+    /// price fill at their own price, highest first; the book is repriced
+    /// after each fill.
+    pub fn tick(&mut self, tick: &PriceTick) -> Result<Vec<ExchangeFill>> {
+        self.last_price = Some(tick.price);
+        let mut fills = Vec::new();
+        while let Some(order) = self
+            .book
+            .iter()
+            .filter(|o| o.price >= tick.price)
+            .max_by(|a, b| a.price.total_cmp(&b.price))
+            .cloned()
+        {
+            let fee = order.price * order.quantity * self.fee_rate;
+            self.state.record(
+                Some(order.level),
+                Execution {
+                    price: order.price,
+                    quantity: order.quantity,
+                    fee,
+                    timestamp: tick.timestamp,
+                },
+            )?;
+            self.book = build_orders(&self.state.remaining(), &self.options)?;
+            fills.push(ExchangeFill {
+                order,
+                timestamp: tick.timestamp,
+                market_price: tick.price,
+                fee,
+            });
+        }
+        self.fills.extend(fills.iter().cloned());
+        Ok(fills)
+    }
+
+    /// Replay a whole feed, returning every fill in order
+    pub fn replay(&mut self, feed: &[PriceTick]) -> Result<Vec<ExchangeFill>> {
+        let mut fills = Vec::new();
+        for tick in feed {
+            fills.extend(self.tick(tick)?);
+        }
+        Ok(fills)
+    }
+
+    /// Orders still resting, in ladder order
+    pub fn book(&self) -> &[Order] {
+        &self.book
+    }
+
+    /// Every fill so far, in order
+    pub fn fills(&self) -> &[ExchangeFill] {
+        &self.fills
+    }
+
+    pub fn state(&self) -> &GridState {
+        &self.state
+    }
+
+    pub fn into_state(self) -> GridState {
+        self.state
+    }
+
+    /// Last market price seen
+    pub fn last_price(&self) -> Option<f64> {
+        self.last_price
+    }
+
+    /// PnL marked at `price`
+    pub fn pnl(&self, price: f64) -> Pnl {
+        self.state.pnl(price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::{GridType, PositionMode};
+
+    fn exchange(grid_type: GridType, fee_rate: f64) -> MockExchange {
+        let mut config = Config::test(
+            &[1.0, 2.0, 5.0],
+            PositionMode::IncrementMultiple,
+            1.0,
+            Some(2.0),
+        );
+        config.base.grid_type = grid_type;
+        let options = OrderOptions {
+            tick_size: Some(0.01),
+            ..OrderOptions::new("BTCUSDT")
+        };
+        MockExchange::new(GridState::new(config, None).unwrap(), options, fee_rate).unwrap()
+    }
+
+    fn ticks(prices: &[f64]) -> Vec<PriceTick> {
+        let start: DateTime<Utc> = "2024-05-01T00:00:00Z".parse().unwrap();
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, &price)| PriceTick {
+                timestamp: start + chrono::Duration::minutes(i as i64),
+                price,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fills_and_pnl() {
+        let mut exchange = exchange(GridType::Fixed, 0.001);
+        assert_eq!(exchange.book().len(), 3);
+
+        let fills = exchange.replay(&ticks(&[100.0, 99.5, 97.5, 99.0])).unwrap();
+        // The drop to 97.5 crosses the orders at 99 and 98 in one tick
+        assert_eq!(fills.len(), 2);
+        assert_eq!((fills[0].order.level, fills[0].order.price), (1, 99.0));
+        assert_eq!((fills[1].order.level, fills[1].order.quantity), (2, 2.0));
+        assert_eq!(fills[1].market_price, 97.5);
+        assert!((fills[0].fee - 0.099).abs() < 1e-12);
+
+        // The calculator saw both fills, so the last order is sized from them
+        assert_eq!(exchange.book().len(), 1);
+        assert_eq!(exchange.book()[0].quantity, 4.0);
+        assert_eq!(exchange.state().fills().len(), 2);
+
+        let pnl = exchange.pnl(exchange.last_price().unwrap());
+        assert_eq!(pnl.position, 3.0);
+        let cost = 99.0 + 196.0 + pnl.fees;
+        assert!((pnl.cost - cost).abs() < 1e-9);
+        assert!((pnl.unrealized - (297.0 - cost)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_average_grid_reprices_book() {
+        let mut exchange = exchange(GridType::Average, 0.0);
+        exchange.replay(&ticks(&[99.0])).unwrap();
+        // Level 2 is now 2% below the average price of 99
+        assert_eq!(exchange.book()[0].price, 97.02);
+        exchange.replay(&ticks(&[97.02])).unwrap();
+        assert_eq!(exchange.fills().len(), 2);
+    }
+
+    #[test]
+    fn test_no_fills_above_book() {
+        let mut exchange = exchange(GridType::Fixed, 0.0);
+        assert!(exchange.replay(&ticks(&[101.0, 99.01])).unwrap().is_empty());
+        assert_eq!(exchange.pnl(99.01).position, 0.0);
+
+        let config = exchange.into_state().config.clone();
+        let state = GridState::new(config, None).unwrap();
+        assert!(MockExchange::new(state, OrderOptions::new("X"), 1.5).is_err());
+    }
+}
@@ -0,0 +1,450 @@
+use crate::error::{Error, Result};
+use crate::import::{csv_error, parse_number, parse_timestamp};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// A market price at a point in time
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PriceTick {
+    pub timestamp: DateTime<Utc>,
+    pub price: f64,
+}
+
+/// Read a price feed from CSV with `timestamp` and `price` columns. Other
+/// columns are ignored; ticks are returned oldest first.
+pub fn read_prices<R: Read>(reader: R) -> Result<Vec<PriceTick>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let index = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name))
+            .ok_or_else(|| Error::Parse {
+                path: None,
+                message: format!("Column '{}' not found in price feed", name),
+            })
+    };
+    let timestamp = index("timestamp")?;
+    let price = index("price")?;
+
+    let mut ticks = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let line = record.position().map_or(0, |p| p.line());
+        let row_error = |e: Error| e.at(format_args!("line {}", line));
+        let field = |i: usize| record.get(i).unwrap_or_default();
+        let tick = PriceTick {
+            timestamp: parse_timestamp(field(timestamp), None).map_err(row_error)?,
+            price: parse_number(field(price), false).map_err(row_error)?,
+        };
+        check_price(tick.price).map_err(|message| row_error(Error::parse(message)))?;
+        ticks.push(tick);
+    }
+    ticks.sort_by_key(|t| t.timestamp);
+    Ok(ticks)
+}
+
+/// Read a CSV price feed from a file
+pub fn read_price_file<P: AsRef<Path>>(path: P) -> Result<Vec<PriceTick>> {
+    let path = path.as_ref();
+    let file = fs::File::open(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    read_prices(file).map_err(|e| e.in_file(path))
+}
+
+/// A reproducible random walk of prices
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntheticFeed {
+    pub start_price: f64,
+    pub ticks: usize,
+    /// Largest move per tick, in percent
+    pub volatility: f64,
+    /// Trend added to every move, in percent per tick
+    pub drift: f64,
+    /// Feeds with the same seed and settings produce the same prices
+    pub seed: u64,
+    pub start: DateTime<Utc>,
+    pub interval: Duration,
+}
+
+impl SyntheticFeed {
+    pub fn new(start_price: f64, ticks: usize, start: DateTime<Utc>) -> Self {
+        Self {
+            start_price,
+            ticks,
+            volatility: 1.0,
+            drift: 0.0,
+            seed: 1,
+            start,
+            interval: Duration::minutes(1),
+        }
+    }
+
+    /// Every tick of the walk, starting at `start_price`
+    pub fn generate(&self) -> Result<Vec<PriceTick>> {
+        check_price(self.start_price).map_err(|message| Error::InvalidField {
+            field: "start_price".to_string(),
+            strategy: None,
+            message,
+        })?;
+        if !(0.0..100.0).contains(&self.volatility) {
+            return Err(Error::InvalidField {
+                field: "volatility".to_string(),
+                strategy: None,
+                message: format!(
+                    "Volatility must be between 0 and 100 percent, got {}",
+                    self.volatility
+                ),
+            });
+        }
+
+        // xorshift64*; the seed is mixed so that small seeds differ early on
+        let mut state = self.seed ^ 0x9E37_79B9_7F4A_7C15;
+        let mut uniform = move || {
+            state ^= state >> 12;
+            state ^= state << 25;
+            state ^= state >> 27;
+            let bits = state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+            bits as f64 / (1u64 << 53) as f64
+        };
+
+        let mut price = self.start_price;
+        let mut ticks = Vec::with_capacity(self.ticks);
+        for i in 0..self.ticks {
+            if i > 0 {
+                let change = self.volatility * (2.0 * uniform() - 1.0) + self.drift;
+                // Prices stay positive however strong the drift
+                price = (price * (1.0 + change / 100.0)).max(f64::MIN_POSITIVE);
+            }
+            ticks.push(PriceTick {
+                timestamp: self.start + self.interval * i as i32,
+                price,
+            });
+        }
+        Ok(ticks)
+    }
+}
+
+/// Parse one line of a live feed: a bare price, `timestamp,price`, or a
+/// JSON object with a `price` and optionally a `timestamp` (text or epoch
+/// milliseconds). Lines without a timestamp are stamped with `now`. Blank
+/// lines and `#` comments give `None`.
+pub fn parse_line(line: &str, now: DateTime<Utc>) -> Result<Option<PriceTick>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let tick = if line.starts_with('{') {
+        let value: Value = serde_json::from_str(line).map_err(|e| Error::parse(e.to_string()))?;
+        let price = match &value["price"] {
+            Value::Number(n) => n.as_f64().unwrap_or(f64::NAN),
+            // Exchanges often send prices as strings to keep their precision
+            Value::String(s) => parse_number(s, false)?,
+            _ => return Err(Error::parse(format!("No price in '{}'", line))),
+        };
+        let timestamp = match &value["timestamp"] {
+            Value::Null => now,
+            Value::String(s) => parse_timestamp(s, None)?,
+            Value::Number(n) => n
+                .as_i64()
+                .and_then(DateTime::from_timestamp_millis)
+                .ok_or_else(|| Error::parse(format!("Invalid timestamp {}", n)))?,
+            other => return Err(Error::parse(format!("Invalid timestamp {}", other))),
+        };
+        PriceTick { timestamp, price }
+    } else {
+        match line.split_once(',') {
+            Some((timestamp, price)) => PriceTick {
+                timestamp: parse_timestamp(timestamp.trim(), None)?,
+                price: parse_number(price.trim(), false)?,
+            },
+            None => PriceTick {
+                timestamp: now,
+                price: parse_number(line, false)?,
+            },
+        }
+    };
+    check_price(tick.price).map_err(Error::parse)?;
+    Ok(Some(tick))
+}
+
+/// Where a live feed reads its lines from
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedSource {
+    Stdin,
+    /// A file read from the start; with `follow`, lines appended later are
+    /// read as they arrive, like `tail -f`
+    File {
+        path: PathBuf,
+        follow: bool,
+    },
+    /// A TCP server sending one price per line, e.g. `127.0.0.1:9000`
+    Tcp(String),
+}
+
+impl fmt::Display for FeedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedSource::Stdin => f.write_str("stdin"),
+            FeedSource::File { path, .. } => write!(f, "{}", path.display()),
+            FeedSource::Tcp(address) => write!(f, "tcp://{}", address),
+        }
+    }
+}
+
+/// Ticks read from a live source as they arrive. Ends when the source
+/// does; a followed file never ends.
+pub struct LiveFeed {
+    source: FeedSource,
+    lines: Box<dyn Iterator<Item = io::Result<String>> + Send>,
+    line: usize,
+}
+
+impl LiveFeed {
+    pub fn open(source: FeedSource) -> Result<Self> {
+        let io_error = |source: io::Error, path: &FeedSource| Error::Io {
+            path: PathBuf::from(path.to_string()),
+            source,
+        };
+        let lines: Box<dyn Iterator<Item = io::Result<String>> + Send> = match &source {
+            FeedSource::Stdin => Box::new(BufReader::new(io::stdin()).lines()),
+            FeedSource::File { path, follow } => {
+                let file = fs::File::open(path).map_err(|e| io_error(e, &source))?;
+                if *follow {
+                    Box::new(Tail::new(file))
+                } else {
+                    Box::new(BufReader::new(file).lines())
+                }
+            }
+            FeedSource::Tcp(address) => {
+                let stream = TcpStream::connect(address).map_err(|e| io_error(e, &source))?;
+                Box::new(BufReader::new(stream).lines())
+            }
+        };
+        Ok(Self {
+            source,
+            lines,
+            line: 0,
+        })
+    }
+
+    pub fn source(&self) -> &FeedSource {
+        &self.source
+    }
+}
+
+impl Iterator for LiveFeed {
+    /// A tick, or why a line could not be read. Reading may continue
+    /// after a line that does not parse.
+    type Item = Result<PriceTick>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(source) => {
+                    return Some(Err(Error::Io {
+                        path: PathBuf::from(self.source.to_string()),
+                        source,
+                    }))
+                }
+            };
+            self.line += 1;
+            match parse_line(&line, Utc::now()) {
+                Ok(Some(tick)) => return Some(Ok(tick)),
+                Ok(None) => continue,
+                Err(e) => {
+                    return Some(Err(e.at(format_args!("{} line {}", self.source, self.line))))
+                }
+            }
+        }
+    }
+}
+
+/// Lines of a file, waiting at the end for more to be appended
+struct Tail {
+    reader: BufReader<fs::File>,
+    partial: String,
+}
+
+impl Tail {
+    /// How often the end of the file is checked for new lines
+    const POLL: std::time::Duration = std::time::Duration::from_millis(100);
+
+    fn new(file: fs::File) -> Self {
+        Self {
+            reader: BufReader::new(file),
+            partial: String::new(),
+        }
+    }
+}
+
+impl Iterator for Tail {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.read_line(&mut self.partial) {
+                // A line still being written is kept until its newline arrives
+                Ok(0) => thread::sleep(Self::POLL),
+                Ok(_) if self.partial.ends_with('\n') => {
+                    let line = std::mem::take(&mut self.partial);
+                    return Some(Ok(line.trim_end().to_string()));
+                }
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+fn check_price(price: f64) -> std::result::Result<(), String> {
+    if price <= 0.0 || !price.is_finite() {
+        return Err(format!("Price must be positive, got {}", price));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_prices() {
+        let csv = "timestamp,symbol,price\n\
+                   2024-05-01T12:01:00Z,BTCUSDT,99.5\n\
+                   2024-05-01 12:00:00,BTCUSDT,\"1,000.25\"\n";
+        let ticks = read_prices(csv.as_bytes()).unwrap();
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(ticks[0].price, 1000.25);
+        assert_eq!(ticks[1].price, 99.5);
+
+        let err = read_prices("timestamp,price\n2024-05-01T12:00:00Z,-1\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+        assert!(read_prices("time,price\n".as_bytes()).is_err());
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/prices/btcusdt.csv");
+        let ticks = read_price_file(path).unwrap();
+        assert_eq!(ticks.len(), 7);
+        assert_eq!(ticks[4].price, 97.6);
+    }
+
+    #[test]
+    fn test_synthetic_feed_is_reproducible() {
+        let start = "2024-05-01T00:00:00Z".parse().unwrap();
+        let feed = SyntheticFeed {
+            volatility: 2.0,
+            ..SyntheticFeed::new(100.0, 50, start)
+        };
+        let ticks = feed.generate().unwrap();
+        assert_eq!(ticks.len(), 50);
+        assert_eq!(ticks[0].price, 100.0);
+        assert_eq!(ticks[2].timestamp, start + Duration::minutes(2));
+        assert_eq!(ticks, feed.generate().unwrap());
+        for pair in ticks.windows(2) {
+            let change = (pair[1].price / pair[0].price - 1.0).abs();
+            assert!(change <= 0.02 + 1e-12, "{}", change);
+        }
+
+        let other = SyntheticFeed {
+            seed: 2,
+            ..feed.clone()
+        };
+        assert_ne!(ticks, other.generate().unwrap());
+
+        let falling = SyntheticFeed {
+            volatility: 0.0,
+            drift: -1.0,
+            ..feed
+        };
+        let ticks = falling.generate().unwrap();
+        assert!((ticks[1].price - 99.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let now = "2024-05-01T00:00:00Z".parse().unwrap();
+        let price = |line| parse_line(line, now).unwrap().map(|t| t.price);
+        assert_eq!(price("  101.5 "), Some(101.5));
+        assert_eq!(price("2024-05-01T12:00:00Z, 99"), Some(99.0));
+        assert_eq!(
+            price(r#"{"symbol": "BTCUSDT", "price": "98.25"}"#),
+            Some(98.25)
+        );
+        assert_eq!(price(""), None);
+        assert_eq!(price("# comment"), None);
+
+        let tick = parse_line(r#"{"price": 97, "timestamp": 1714564800000}"#, now)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            tick.timestamp,
+            "2024-05-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(parse_line("100", now).unwrap().unwrap().timestamp, now);
+
+        assert!(parse_line("abc", now).is_err());
+        assert!(parse_line("-5", now).is_err());
+        assert!(parse_line(r#"{"last": 5}"#, now).is_err());
+    }
+
+    #[test]
+    fn test_tail_file() {
+        let path = std::env::temp_dir().join(format!("grid-feed-{}.txt", std::process::id()));
+        fs::write(&path, "100\n").unwrap();
+        let mut feed = LiveFeed::open(FeedSource::File {
+            path: path.clone(),
+            follow: true,
+        })
+        .unwrap();
+        assert_eq!(feed.next().unwrap().unwrap().price, 100.0);
+
+        let writer = {
+            let path = path.clone();
+            thread::spawn(move || {
+                use std::io::Write;
+                let mut file = fs::OpenOptions::new().append(true).open(path).unwrap();
+                // Written in pieces, as a live writer might
+                file.write_all(b"not a price\n99.").unwrap();
+                file.flush().unwrap();
+                thread::sleep(std::time::Duration::from_millis(150));
+                file.write_all(b"5\n").unwrap();
+            })
+        };
+        let err = feed.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+        assert_eq!(feed.next().unwrap().unwrap().price, 99.5);
+        writer.join().unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_tcp_source() {
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(b"100\n\n98.5\n").unwrap();
+        });
+
+        let feed = LiveFeed::open(FeedSource::Tcp(address)).unwrap();
+        let prices: Vec<f64> = feed.map(|t| t.unwrap().price).collect();
+        assert_eq!(prices, [100.0, 98.5]);
+        server.join().unwrap();
+    }
+}
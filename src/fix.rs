@@ -0,0 +1,208 @@
+use crate::error::{Error, Result};
+use crate::import::Side;
+use crate::orders::{Order, OrderOptions};
+use chrono::{DateTime, Utc};
+
+pub const BEGIN_STRING: &str = "FIX.4.4";
+
+/// Field delimiter of FIX messages
+pub const SOH: char = '\x01';
+
+/// Header values identifying the session the orders are sent on
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixSession {
+    /// SenderCompID (49)
+    pub sender_comp_id: String,
+    /// TargetCompID (56)
+    pub target_comp_id: String,
+    /// Account (1), omitted if unset
+    pub account: Option<String>,
+    /// MsgSeqNum (34) of the first message; later messages count up
+    pub first_seq_num: u64,
+}
+
+impl FixSession {
+    pub fn new(sender_comp_id: impl Into<String>, target_comp_id: impl Into<String>) -> Self {
+        Self {
+            sender_comp_id: sender_comp_id.into(),
+            target_comp_id: target_comp_id.into(),
+            account: None,
+            first_seq_num: 1,
+        }
+    }
+}
+
+/// One `NewOrderSingle` (35=D) per order: a GTC limit order with the
+/// order's client order ID, sent at `time`
+pub fn new_order_singles(
+    orders: &[Order],
+    options: &OrderOptions,
+    session: &FixSession,
+    time: DateTime<Utc>,
+) -> Result<Vec<String>> {
+    check_value("SenderCompID", &session.sender_comp_id)?;
+    check_value("TargetCompID", &session.target_comp_id)?;
+    if let Some(account) = &session.account {
+        check_value("Account", account)?;
+    }
+    check_value("Symbol", &options.symbol)?;
+
+    let timestamp = time.format("%Y%m%d-%H:%M:%S%.3f").to_string();
+    let messages = orders.iter().enumerate().map(|(i, order)| {
+        let mut body = vec![
+            (35, "D".to_string()),
+            (49, session.sender_comp_id.clone()),
+            (56, session.target_comp_id.clone()),
+            (34, (session.first_seq_num + i as u64).to_string()),
+            (52, timestamp.clone()),
+            (11, order.client_order_id.clone()),
+        ];
+        if let Some(account) = &session.account {
+            body.push((1, account.clone()));
+        }
+        body.extend([
+            // HandlInst: automated execution, no broker intervention
+            (21, "1".to_string()),
+            (55, order.symbol.clone()),
+            (54, side_code(order.side).to_string()),
+            (60, timestamp.clone()),
+            (38, options.format_quantity(order.quantity)),
+            // OrdType: limit
+            (40, "2".to_string()),
+            (44, options.format_price(order.price)),
+            // TimeInForce: good till cancel
+            (59, "1".to_string()),
+        ]);
+        encode(&body)
+    });
+    Ok(messages.collect())
+}
+
+/// Frame body fields with BeginString, BodyLength and CheckSum
+pub fn encode(body: &[(u32, String)]) -> String {
+    let body: String = body
+        .iter()
+        .map(|(tag, value)| format!("{}={}{}", tag, value, SOH))
+        .collect();
+    let mut message = format!("8={}{}9={}{}{}", BEGIN_STRING, SOH, body.len(), SOH, body);
+    let sum = checksum(message.as_bytes());
+    message.push_str(&format!("10={:03}{}", sum, SOH));
+    message
+}
+
+/// Sum of all bytes modulo 256, as carried in CheckSum (10)
+pub fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+fn side_code(side: Side) -> char {
+    match side {
+        Side::Buy => '1',
+        Side::Sell => '2',
+    }
+}
+
+fn check_value(name: &str, value: &str) -> Result<()> {
+    if value.is_empty() || value.contains(SOH) {
+        return Err(Error::InvalidOrder {
+            message: format!("{} must be a non-empty FIX value, got '{}'", name, value),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orders::build_orders;
+    use crate::state::GridState;
+    use crate::Config;
+    use crate::PositionMode;
+
+    fn messages(session: &FixSession) -> Result<Vec<String>> {
+        let config = Config::test(&[1.0, 2.5], PositionMode::Fixed, 0.5, None);
+        let options = OrderOptions {
+            tick_size: Some(0.01),
+            lot_size: Some(0.001),
+            ..OrderOptions::new("BTC/USD")
+        };
+        let ladder = GridState::new(config, None).unwrap().remaining();
+        let orders = build_orders(&ladder, &options).unwrap();
+        let time = "2024-05-01T12:00:00.250Z".parse().unwrap();
+        new_order_singles(&orders, &options, session, time)
+    }
+
+    /// Split a message into (tag, value) pairs
+    fn fields(message: &str) -> Vec<(&str, &str)> {
+        message
+            .trim_end_matches(SOH)
+            .split(SOH)
+            .map(|field| field.split_once('=').unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_new_order_single() {
+        let session = FixSession {
+            account: Some("ACC1".to_string()),
+            first_seq_num: 7,
+            ..FixSession::new("DESK", "BROKER")
+        };
+        let messages = messages(&session).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].replace(SOH, "|"),
+            "8=FIX.4.4|9=143|35=D|49=DESK|56=BROKER|34=7|52=20240501-12:00:00.250|\
+             11=grid1|1=ACC1|21=1|55=BTC/USD|54=1|60=20240501-12:00:00.250|38=0.500|\
+             40=2|44=99.00|59=1|10=196|"
+        );
+        assert!(fields(&messages[1]).contains(&("34", "8")));
+        assert!(fields(&messages[1]).contains(&("44", "97.50")));
+    }
+
+    #[test]
+    fn test_body_length_and_checksum() {
+        let session = FixSession::new("DESK", "BROKER");
+        for message in messages(&session).unwrap() {
+            let fields = fields(&message);
+            assert_eq!(fields[0], ("8", "FIX.4.4"));
+            assert_eq!(fields[1].0, "9");
+            assert_eq!(fields.last().unwrap().0, "10");
+            assert!(!fields.iter().any(|(tag, _)| *tag == "1"));
+
+            // BodyLength counts from after its own field up to CheckSum
+            let body_start = message.find("35=").unwrap();
+            let trailer = message.rfind("10=").unwrap();
+            assert_eq!(fields[1].1.parse::<usize>().unwrap(), trailer - body_start);
+
+            // CheckSum covers every byte before it, zero-padded to 3 digits
+            let sum: u32 = message.as_bytes()[..trailer]
+                .iter()
+                .map(|b| *b as u32)
+                .sum();
+            let expected = format!("{:03}", sum % 256);
+            assert_eq!(fields.last().unwrap().1, expected);
+        }
+    }
+
+    #[test]
+    fn test_checksum_padding() {
+        assert_eq!(checksum(b"\x01\x02"), 3);
+        assert_eq!(checksum(&[200, 100]), 44);
+        let message = encode(&[(35, "0".to_string())]);
+        assert!(message.ends_with(&format!(
+            "10={:03}{}",
+            checksum(&message.as_bytes()[..message.len() - 7]),
+            SOH
+        )));
+        assert_eq!(message.len() - message.rfind("10=").unwrap(), 7);
+    }
+
+    #[test]
+    fn test_rejects_invalid_values() {
+        let session = FixSession::new("", "BROKER");
+        assert!(messages(&session).is_err());
+        let session = FixSession::new("DESK", "BRO\x01KER");
+        assert!(messages(&session).is_err());
+    }
+}
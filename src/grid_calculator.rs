@@ -1,17 +1,101 @@
-#[derive(Debug, Clone, Copy)]
+use crate::error::Error;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// What grid percentages are measured from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
 pub enum GridType {
+    /// Fixed price grid (relative to initial price)
     Fixed,
+    /// Average price grid (relative to average price)
     Average,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// How the position size at each grid level is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
 pub enum PositionMode {
+    /// Fixed size for each grid level
     Fixed,
+    /// Multiple of current position
     CurrentMultiple,
+    /// Multiple of last increment
     IncrementMultiple,
 }
 
-#[derive(Debug, Clone)]
+impl GridType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GridType::Fixed => "fixed",
+            GridType::Average => "average",
+        }
+    }
+}
+
+impl PositionMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PositionMode::Fixed => "fixed",
+            PositionMode::CurrentMultiple => "current-multiple",
+            PositionMode::IncrementMultiple => "increment-multiple",
+        }
+    }
+
+    /// Whether position sizes are scaled by the multiplier in this mode
+    pub fn uses_multiplier(&self) -> bool {
+        !matches!(self, PositionMode::Fixed)
+    }
+}
+
+impl fmt::Display for GridType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for PositionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GridType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(GridType::Fixed),
+            "average" => Ok(GridType::Average),
+            _ => Err(Error::invalid_field(
+                "base.grid_type",
+                format!("Invalid grid_type: {}. Must be 'fixed' or 'average'", s),
+            )),
+        }
+    }
+}
+
+impl FromStr for PositionMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(PositionMode::Fixed),
+            "current-multiple" => Ok(PositionMode::CurrentMultiple),
+            "increment-multiple" => Ok(PositionMode::IncrementMultiple),
+            _ => Err(Error::invalid_field(
+                "position.mode",
+                format!("Invalid position mode: {}. Must be 'fixed', 'current-multiple', or 'increment-multiple'", s),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct GridResult {
     pub grid_price: f64,
     pub position_size: f64,
@@ -20,13 +104,44 @@ pub struct GridResult {
     pub total_cost: f64,
 }
 
+/// An actual trade executed for a grid level
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Execution {
+    pub price: f64,
+    pub quantity: f64,
+    /// Fee paid, in the same currency as the cost
+    pub fee: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A grid level as planned next to what its executions actually booked
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelFill {
+    pub planned: GridResult,
+    /// `grid_price` is the volume-weighted execution price and
+    /// `position_size` the executed quantity; costs include fees
+    pub executed: GridResult,
+    pub fees: f64,
+}
+
+impl LevelFill {
+    /// How far the execution price is from the planned price, as a
+    /// percentage of the planned price. Positive means paid more.
+    pub fn slippage_percent(&self) -> f64 {
+        (self.executed.grid_price - self.planned.grid_price) / self.planned.grid_price * 100.0
+    }
+}
+
+/// Sizes grid levels one at a time, tracking the position built so far.
+/// The state serializes, so a calculator can be saved and resumed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct GridCalculator {
     initial_price: f64,
     grid_type: GridType,
     position_mode: PositionMode,
     base_size: f64,
     multiplier: f64,
-    
+
     // State tracking
     current_position: f64,
     total_cost: f64,
@@ -58,6 +173,13 @@ impl GridCalculator {
     }
 
     pub fn calculate_grid(&mut self, grid_percent: f64) -> GridResult {
+        let result = self.plan_grid(grid_percent);
+        self.book(result.grid_price, result.position_size, 0.0)
+    }
+
+    /// The price and size of the grid at `grid_percent` given the position
+    /// so far, without updating the state
+    pub fn plan_grid(&self, grid_percent: f64) -> GridResult {
         // Calculate grid price based on type
         let grid_price = match self.grid_type {
             GridType::Fixed => {
@@ -94,19 +216,57 @@ impl GridCalculator {
             }
         };
 
+        let total_position = self.current_position + position_size;
+        let total_cost = self.total_cost + position_size * grid_price;
+        GridResult {
+            grid_price,
+            position_size,
+            total_position,
+            average_price: if total_position > 0.0 {
+                total_cost / total_position
+            } else {
+                self.average_price
+            },
+            total_cost,
+        }
+    }
+
+    /// Fill the grid at `grid_percent` with what actually executed instead
+    /// of the plan. Later levels are sized from the executed position.
+    pub fn record_executions(&mut self, grid_percent: f64, executions: &[Execution]) -> LevelFill {
+        let planned = self.plan_grid(grid_percent);
+        let quantity: f64 = executions.iter().map(|e| e.quantity).sum();
+        let value: f64 = executions.iter().map(|e| e.price * e.quantity).sum();
+        let fees: f64 = executions.iter().map(|e| e.fee).sum();
+        let price = if quantity > 0.0 {
+            value / quantity
+        } else {
+            planned.grid_price
+        };
+
+        let executed = self.book(price, quantity, fees);
+        LevelFill {
+            planned,
+            executed,
+            fees,
+        }
+    }
+
+    /// Add `size` bought at `price` plus `fee` to the position
+    fn book(&mut self, price: f64, size: f64, fee: f64) -> GridResult {
         // Update state
-        self.last_increment = position_size;
-        self.current_position += position_size;
-        self.total_cost += position_size * grid_price;
-        
+        self.last_increment = size;
+        self.current_position += size;
+        self.total_cost += size * price + fee;
+
         // Calculate new average price
         if self.current_position > 0.0 {
             self.average_price = self.total_cost / self.current_position;
         }
 
         let result = GridResult {
-            grid_price,
-            position_size,
+            grid_price: price,
+            position_size: size,
             total_position: self.current_position,
             average_price: self.average_price,
             total_cost: self.total_cost,
@@ -127,6 +287,19 @@ impl GridCalculator {
     pub fn get_history(&self) -> &[GridResult] {
         &self.grid_history
     }
+
+    /// Total size of the position built so far
+    pub fn current_position(&self) -> f64 {
+        self.current_position
+    }
+
+    pub fn average_price(&self) -> f64 {
+        self.average_price
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.total_cost
+    }
 }
 
 #[cfg(test)]
@@ -198,6 +371,48 @@ mod tests {
         assert_eq!(result3.total_position, 900.0);
     }
 
+    #[test]
+    fn test_mode_names_round_trip() {
+        for mode in PositionMode::value_variants() {
+            assert_eq!(mode.as_str().parse::<PositionMode>().unwrap(), *mode);
+        }
+        for grid_type in GridType::value_variants() {
+            assert_eq!(grid_type.as_str().parse::<GridType>().unwrap(), *grid_type);
+        }
+        assert!("current_multiple".parse::<PositionMode>().is_err());
+    }
+
+    #[test]
+    fn test_record_executions() {
+        let mut calc = GridCalculator::new(
+            100.0,
+            GridType::Fixed,
+            PositionMode::CurrentMultiple,
+            100.0,
+            2.0,
+        );
+        let execution = |price: f64, quantity: f64| Execution {
+            price,
+            quantity,
+            fee: 1.0,
+            timestamp: DateTime::UNIX_EPOCH,
+        };
+
+        let fill = calc.record_executions(1.0, &[execution(98.0, 60.0), execution(99.5, 60.0)]);
+        assert_eq!(fill.planned.grid_price, 99.0);
+        assert_eq!(fill.planned.position_size, 100.0);
+        assert_eq!(fill.executed.position_size, 120.0);
+        assert_eq!(fill.executed.grid_price, 98.75);
+        assert_eq!(fill.fees, 2.0);
+        assert_eq!(fill.executed.total_cost, 120.0 * 98.75 + 2.0);
+        assert!((fill.slippage_percent() - (-0.25 / 99.0 * 100.0)).abs() < 1e-9);
+
+        // The next level is sized from the executed position
+        assert_eq!(calc.plan_grid(2.0).position_size, 240.0);
+        assert_eq!(calc.current_position(), 120.0);
+        assert_eq!(calc.average_price(), calc.total_cost() / 120.0);
+    }
+
     #[test]
     fn test_increment_multiple_mode() {
         let mut calc = GridCalculator::new(
@@ -0,0 +1,450 @@
+//! gRPC service for the calculator, defined in `proto/grid_calculator.proto`
+
+use crate::config::{lint, migrate, Config, Strategy};
+use crate::config::{
+    BaseConfig, GridConfig, PartialBase, PartialGrid, PartialPosition, PositionConfig,
+};
+use crate::diagnostics::{self, Diagnostic, Severity};
+use crate::error::Error;
+use crate::grid_calculator::{GridResult, GridType, PositionMode};
+use crate::state::{GridState, LadderLevel};
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+
+/// Types and service stubs generated from the protobuf definition
+pub mod proto {
+    tonic::include_proto!("grid_calculator.v1");
+}
+
+use proto::grid_calculator_server::{GridCalculator as GridCalculatorRpc, GridCalculatorServer};
+
+/// Fills a simulation computes ahead of the client reading them
+const SIMULATE_BUFFER: usize = 16;
+
+/// Implementation of the `GridCalculator` service
+#[derive(Debug, Default, Clone)]
+pub struct Service;
+
+#[tonic::async_trait]
+impl GridCalculatorRpc for Service {
+    async fn calculate(
+        &self,
+        request: Request<proto::CalculateRequest>,
+    ) -> Result<Response<proto::CalculateResponse>, Status> {
+        let request = request.into_inner();
+        let config = resolve(request.config, request.strategy.as_deref())?;
+        let state = GridState::new(config, None).map_err(invalid)?;
+        let levels = state.remaining().iter().map(ladder_level).collect();
+        Ok(Response::new(proto::CalculateResponse { levels }))
+    }
+
+    async fn validate(
+        &self,
+        request: Request<proto::ValidateRequest>,
+    ) -> Result<Response<proto::ValidateResponse>, Status> {
+        let diagnostics = match config(request.into_inner().config) {
+            Ok(config) => config.diagnostics(),
+            Err(e) => vec![Diagnostic::from_error(&e, None)],
+        };
+        Ok(Response::new(proto::ValidateResponse {
+            valid: !diagnostics::has_errors(&diagnostics),
+            diagnostics: diagnostics.iter().map(diagnostic).collect(),
+        }))
+    }
+
+    type SimulateStream = ReceiverStream<Result<proto::Fill, Status>>;
+
+    async fn simulate(
+        &self,
+        request: Request<proto::SimulateRequest>,
+    ) -> Result<Response<Self::SimulateStream>, Status> {
+        let request = request.into_inner();
+        let config = resolve(request.config, request.strategy.as_deref())?;
+        config.validate().map_err(invalid)?;
+        if let Some(price) = request.prices.iter().find(|p| **p <= 0.0 || !p.is_finite()) {
+            return Err(Status::invalid_argument(format!(
+                "Prices must be positive, got {}",
+                price
+            )));
+        }
+
+        // Fills are sent as they are computed; the simulation stops early
+        // once the client drops the stream
+        let (sender, receiver) = mpsc::channel(SIMULATE_BUFFER);
+        tokio::task::spawn_blocking(move || {
+            simulate(&config, &request.prices, |fill| {
+                sender.blocking_send(Ok(fill)).is_ok()
+            });
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// Serve the `GridCalculator` service on an already bound listener
+pub async fn serve_on(listener: TcpListener) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(GridCalculatorServer::new(Service))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await
+}
+
+/// Listen on `address`, e.g. `127.0.0.1:50051`. Port 0 picks a free port;
+/// the address actually bound is returned with the listener.
+pub async fn bind(address: &str) -> crate::Result<(TcpListener, SocketAddr)> {
+    let bind_error = |e: std::io::Error| Error::Bind {
+        address: address.to_string(),
+        message: e.to_string(),
+    };
+    let listener = TcpListener::bind(address).await.map_err(bind_error)?;
+    let local = listener.local_addr().map_err(bind_error)?;
+    Ok((listener, local))
+}
+
+/// Fill the ladder against a price path: each level fills at its grid
+/// price once the market trades at or below it, in ladder order. Each fill
+/// is passed to `emit` as it happens; the simulation stops if it returns
+/// false.
+fn simulate(config: &Config, prices: &[f64], mut emit: impl FnMut(proto::Fill) -> bool) {
+    let mut calculator = config.calculator();
+    let mut levels = config.grid.levels.iter().enumerate().peekable();
+
+    for (tick, &price) in prices.iter().enumerate() {
+        while let Some(&(i, &percent)) = levels.peek() {
+            if calculator.plan_grid(percent).grid_price < price {
+                break;
+            }
+            let result = calculator.calculate_grid(percent);
+            let fill = proto::Fill {
+                tick: tick as u32,
+                market_price: price,
+                result: Some(grid_result(i + 1, percent, &result)),
+            };
+            if !emit(fill) {
+                return;
+            }
+            levels.next();
+        }
+    }
+}
+
+/// The config of the request, or the named strategy of it, with denied
+/// lints rejected
+fn resolve(config: Option<proto::Config>, strategy: Option<&str>) -> Result<Config, Status> {
+    let config = self::config(config).map_err(invalid)?;
+    let denied: Vec<String> = lint::lint(&config)
+        .into_iter()
+        .filter(|d| d.severity == Severity::Error)
+        .map(|d| d.to_string())
+        .collect();
+    if !denied.is_empty() {
+        return Err(Status::failed_precondition(format!(
+            "Config violates denied lints: {}",
+            denied.join("; ")
+        )));
+    }
+
+    match strategy {
+        Some(name) => config
+            .strategy(name)
+            .and_then(|s| s.to_config(&config))
+            .map_err(|e| match e {
+                Error::StrategyNotFound { .. } => Status::not_found(e.to_string()),
+                e => invalid(e),
+            }),
+        None => Ok(config),
+    }
+}
+
+fn invalid(error: Error) -> Status {
+    Status::invalid_argument(error.to_string())
+}
+
+fn config(config: Option<proto::Config>) -> crate::Result<Config> {
+    let config = config.ok_or_else(|| Error::invalid_field("config", "Config is required"))?;
+    let missing = |field: &str| Error::invalid_field(field, format!("{} is required", field));
+    let base = config.base.ok_or_else(|| missing("base"))?;
+    let grid = config.grid.ok_or_else(|| missing("grid"))?;
+    let position = config.position.ok_or_else(|| missing("position"))?;
+
+    Ok(Config {
+        version: match config.version {
+            0 => migrate::CURRENT_VERSION,
+            version => version,
+        },
+        include: None,
+        base: BaseConfig {
+            initial_price: base.initial_price,
+            grid_type: grid_type(base.grid_type, "base.grid_type")?
+                .ok_or_else(|| missing("base.grid_type"))?,
+        },
+        grid: GridConfig {
+            levels: grid.levels,
+        },
+        position: PositionConfig {
+            mode: position_mode(position.mode, "position.mode")?
+                .ok_or_else(|| missing("position.mode"))?,
+            base_size: position.base_size,
+            multiplier: position.multiplier,
+        },
+        strategies: match config.strategies.len() {
+            0 => None,
+            _ => Some(
+                config
+                    .strategies
+                    .into_iter()
+                    .map(strategy)
+                    .collect::<crate::Result<_>>()?,
+            ),
+        },
+        lints: None,
+        alerts: None,
+        portfolio: None,
+    })
+}
+
+fn strategy(strategy: proto::Strategy) -> crate::Result<Strategy> {
+    let base = strategy.base.unwrap_or_default();
+    let grid = strategy.grid.unwrap_or_default();
+    let position = strategy.position.unwrap_or_default();
+    let in_strategy = |e: Error| e.in_strategy(&strategy.name);
+
+    Ok(Strategy {
+        base: PartialBase {
+            initial_price: base.initial_price,
+            grid_type: grid_type(base.grid_type, "base.grid_type").map_err(in_strategy)?,
+        },
+        grid: PartialGrid {
+            levels: (!grid.levels.is_empty()).then_some(grid.levels),
+        },
+        position: PartialPosition {
+            mode: position_mode(position.mode, "position.mode").map_err(in_strategy)?,
+            base_size: position.base_size,
+            multiplier: position.multiplier,
+        },
+        name: strategy.name,
+        extends: strategy.extends,
+    })
+}
+
+/// `None` for the unspecified value
+fn grid_type(value: i32, field: &str) -> crate::Result<Option<GridType>> {
+    match proto::GridType::try_from(value) {
+        Ok(proto::GridType::Unspecified) => Ok(None),
+        Ok(proto::GridType::Fixed) => Ok(Some(GridType::Fixed)),
+        Ok(proto::GridType::Average) => Ok(Some(GridType::Average)),
+        Err(_) => Err(Error::invalid_field(
+            field,
+            format!("Unknown grid type {}", value),
+        )),
+    }
+}
+
+/// `None` for the unspecified value
+fn position_mode(value: i32, field: &str) -> crate::Result<Option<PositionMode>> {
+    match proto::PositionMode::try_from(value) {
+        Ok(proto::PositionMode::Unspecified) => Ok(None),
+        Ok(proto::PositionMode::Fixed) => Ok(Some(PositionMode::Fixed)),
+        Ok(proto::PositionMode::CurrentMultiple) => Ok(Some(PositionMode::CurrentMultiple)),
+        Ok(proto::PositionMode::IncrementMultiple) => Ok(Some(PositionMode::IncrementMultiple)),
+        Err(_) => Err(Error::invalid_field(
+            field,
+            format!("Unknown position mode {}", value),
+        )),
+    }
+}
+
+fn ladder_level(level: &LadderLevel) -> proto::GridResult {
+    grid_result(level.level, level.percent, &level.result)
+}
+
+fn grid_result(level: usize, percent: f64, result: &GridResult) -> proto::GridResult {
+    proto::GridResult {
+        level: level as u32,
+        percent,
+        grid_price: result.grid_price,
+        position_size: result.position_size,
+        total_position: result.total_position,
+        average_price: result.average_price,
+        total_cost: result.total_cost,
+    }
+}
+
+fn diagnostic(diagnostic: &Diagnostic) -> proto::Diagnostic {
+    let severity = match diagnostic.severity {
+        Severity::Warning => proto::Severity::Warning,
+        Severity::Error => proto::Severity::Error,
+    };
+    proto::Diagnostic {
+        severity: severity.into(),
+        path: diagnostic.path.clone(),
+        message: diagnostic.message.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::proto::grid_calculator_client::GridCalculatorClient;
+    use super::*;
+    use tonic::transport::Channel;
+
+    fn config() -> proto::Config {
+        proto::Config {
+            version: 0,
+            base: Some(proto::BaseConfig {
+                initial_price: 100.0,
+                grid_type: proto::GridType::Fixed.into(),
+            }),
+            grid: Some(proto::GridConfig {
+                levels: vec![1.0, 2.0, 5.0],
+            }),
+            position: Some(proto::PositionConfig {
+                mode: proto::PositionMode::IncrementMultiple.into(),
+                base_size: 10.0,
+                multiplier: Some(2.0),
+            }),
+            strategies: vec![proto::Strategy {
+                name: "wide".to_string(),
+                extends: Some("main".to_string()),
+                grid: Some(proto::PartialGrid {
+                    levels: vec![10.0, 20.0],
+                }),
+                ..Default::default()
+            }],
+        }
+    }
+
+    /// Start the service on a free localhost port and connect to it
+    async fn client() -> GridCalculatorClient<Channel> {
+        let (listener, address) = bind("127.0.0.1:0").await.unwrap();
+        tokio::spawn(serve_on(listener));
+        GridCalculatorClient::connect(format!("http://{}", address))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_calculate() {
+        let mut client = client().await;
+        let response = client
+            .calculate(proto::CalculateRequest {
+                config: Some(config()),
+                strategy: None,
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.levels.len(), 3);
+        assert_eq!(response.levels[1].level, 2);
+        assert_eq!(response.levels[1].grid_price, 98.0);
+        assert_eq!(response.levels[1].position_size, 20.0);
+        assert_eq!(response.levels[2].total_position, 70.0);
+
+        let response = client
+            .calculate(proto::CalculateRequest {
+                config: Some(config()),
+                strategy: Some("wide".to_string()),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.levels[1].grid_price, 80.0);
+
+        let status = client
+            .calculate(proto::CalculateRequest {
+                config: Some(config()),
+                strategy: Some("none".to_string()),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let mut invalid = config();
+        invalid.grid = Some(proto::GridConfig {
+            levels: vec![150.0],
+        });
+        let status = client
+            .calculate(proto::CalculateRequest {
+                config: Some(invalid),
+                strategy: None,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(
+            status.message().contains("grid.levels"),
+            "{}",
+            status.message()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate() {
+        let mut client = client().await;
+        let response = client
+            .validate(proto::ValidateRequest {
+                config: Some(config()),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.valid, "{:?}", response.diagnostics);
+
+        let mut invalid = config();
+        invalid.strategies[0].extends = Some("missing".to_string());
+        invalid.position.as_mut().unwrap().mode = 0;
+        let response = client
+            .validate(proto::ValidateRequest {
+                config: Some(invalid),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!response.valid);
+        assert_eq!(response.diagnostics[0].severity(), proto::Severity::Error);
+        assert_eq!(
+            response.diagnostics[0].path.as_deref(),
+            Some("position.mode")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_simulate_streams_fills() {
+        let mut client = client().await;
+        let mut stream = client
+            .simulate(proto::SimulateRequest {
+                config: Some(config()),
+                strategy: None,
+                prices: vec![100.0, 99.5, 97.0, 98.5, 96.0],
+            })
+            .await
+            .unwrap()
+            .into_inner();
+
+        let mut fills = Vec::new();
+        while let Some(fill) = stream.message().await.unwrap() {
+            fills.push(fill);
+        }
+        // 99 and 98 fill on the drop to 97; 95 is never reached
+        assert_eq!(fills.len(), 2);
+        assert_eq!((fills[0].tick, fills[0].market_price), (2, 97.0));
+        assert_eq!(fills[1].tick, 2);
+        let result = fills[1].result.as_ref().unwrap();
+        assert_eq!(
+            (result.level, result.grid_price, result.total_position),
+            (2, 98.0, 30.0)
+        );
+
+        let status = client
+            .simulate(proto::SimulateRequest {
+                config: Some(config()),
+                strategy: None,
+                prices: vec![-1.0],
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,448 @@
+use crate::error::{Error, Result};
+use crate::grid_calculator::Execution;
+use crate::state::GridState;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Trade history exports with a built-in column mapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// Binance spot trade history
+    Binance,
+    /// Coinbase Advanced fills
+    Coinbase,
+    /// Kraken trades export
+    Kraken,
+}
+
+impl Layout {
+    pub fn mapping(&self) -> ColumnMapping {
+        let column = |name: &str| Some(name.to_string());
+        match self {
+            Layout::Binance => ColumnMapping {
+                timestamp: "Date(UTC)".to_string(),
+                price: "Price".to_string(),
+                quantity: "Executed".to_string(),
+                fee: column("Fee"),
+                side: column("Side"),
+                symbol: column("Pair"),
+                ..ColumnMapping::default()
+            },
+            Layout::Coinbase => ColumnMapping {
+                timestamp: "created at".to_string(),
+                price: "price".to_string(),
+                quantity: "size".to_string(),
+                fee: column("fee"),
+                side: column("side"),
+                symbol: column("product"),
+                ..ColumnMapping::default()
+            },
+            Layout::Kraken => ColumnMapping {
+                timestamp: "time".to_string(),
+                price: "price".to_string(),
+                quantity: "vol".to_string(),
+                fee: column("fee"),
+                side: column("type"),
+                symbol: column("pair"),
+                ..ColumnMapping::default()
+            },
+        }
+    }
+}
+
+/// Which CSV columns hold each trade field, by header name. Read from a
+/// TOML file for exports without a built-in layout.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnMapping {
+    pub timestamp: String,
+    pub price: String,
+    pub quantity: String,
+    /// Fee column, in the quote currency unless the value names its asset
+    /// (see `parse_fee`); fees are zero if unset
+    #[serde(default)]
+    pub fee: Option<String>,
+    /// Buy/sell column; every trade is a buy if unset
+    #[serde(default)]
+    pub side: Option<String>,
+    /// Trading pair column, used to filter trades by symbol
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// `chrono` format of the timestamp column. RFC 3339 and
+    /// `YYYY-MM-DD HH:MM:SS[.fff]` in UTC are accepted if unset.
+    #[serde(default)]
+    pub time_format: Option<String>,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Numbers use a comma as the decimal separator, e.g. `99,02`
+    #[serde(default)]
+    pub decimal_comma: bool,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            timestamp: String::new(),
+            price: String::new(),
+            quantity: String::new(),
+            fee: None,
+            side: None,
+            symbol: None,
+            time_format: None,
+            delimiter: default_delimiter(),
+            decimal_comma: false,
+        }
+    }
+}
+
+impl ColumnMapping {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        toml::from_str(&contents).map_err(|e| Error::Parse {
+            path: Some(path.to_path_buf()),
+            message: e.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+        }
+    }
+}
+
+/// One row of a trade export
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    pub symbol: Option<String>,
+    pub side: Side,
+    pub execution: Execution,
+}
+
+/// Read every trade from a CSV export, oldest first
+pub fn read_trades<R: Read>(reader: R, mapping: &ColumnMapping) -> Result<Vec<Trade>> {
+    let delimiter = u8::try_from(mapping.delimiter).map_err(|_| Error::Parse {
+        path: None,
+        message: format!(
+            "Delimiter must be an ASCII character, got '{}'",
+            mapping.delimiter
+        ),
+    })?;
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let index = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| Error::Parse {
+                path: None,
+                message: format!("Column '{}' not found in trade export", name),
+            })
+    };
+    let optional = |name: &Option<String>| name.as_deref().map(index).transpose();
+    let timestamp = index(&mapping.timestamp)?;
+    let price = index(&mapping.price)?;
+    let quantity = index(&mapping.quantity)?;
+    let fee = optional(&mapping.fee)?;
+    let side = optional(&mapping.side)?;
+    let symbol = optional(&mapping.symbol)?;
+
+    let mut trades = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let line = record.position().map_or(0, |p| p.line());
+        let row_error = |e: Error| e.at(format_args!("line {}", line));
+        let field = |i: usize| record.get(i).unwrap_or_default();
+        let number = |i: usize| parse_number(field(i), mapping.decimal_comma).map_err(row_error);
+        let execution_price = number(price)?;
+        let execution_fee = match fee {
+            Some(i) => parse_fee(
+                field(i),
+                execution_price,
+                field(quantity),
+                symbol.map(field),
+                mapping.decimal_comma,
+            )
+            .map_err(row_error)?,
+            None => 0.0,
+        };
+
+        trades.push(Trade {
+            symbol: symbol.map(|i| field(i).to_string()),
+            side: match side {
+                Some(i) => parse_side(field(i)).map_err(row_error)?,
+                None => Side::Buy,
+            },
+            execution: Execution {
+                price: execution_price,
+                quantity: number(quantity)?,
+                fee: execution_fee,
+                timestamp: parse_timestamp(field(timestamp), mapping.time_format.as_deref())
+                    .map_err(row_error)?,
+            },
+        });
+    }
+    trades.sort_by_key(|t| t.execution.timestamp);
+    Ok(trades)
+}
+
+/// Read a CSV export from a file
+pub fn read_file<P: AsRef<Path>>(path: P, mapping: &ColumnMapping) -> Result<Vec<Trade>> {
+    let path = path.as_ref();
+    let file = fs::File::open(path).map_err(|source| Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    read_trades(file, mapping).map_err(|e| e.in_file(path))
+}
+
+/// Outcome of importing trades into a grid
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Imported {
+    /// Level each recorded buy was matched to, in import order
+    pub levels: Vec<usize>,
+    pub sells: usize,
+    /// Trades for another symbol
+    pub other_symbols: usize,
+}
+
+/// Record every buy in `trades` as an execution, matched to levels by
+/// price. With `symbol` set, trades for other symbols are skipped.
+pub fn import(state: &mut GridState, trades: Vec<Trade>, symbol: Option<&str>) -> Result<Imported> {
+    let mut imported = Imported::default();
+    for trade in trades {
+        if symbol.is_some_and(|s| trade.symbol.as_deref() != Some(s)) {
+            imported.other_symbols += 1;
+        } else if trade.side == Side::Sell {
+            imported.sells += 1;
+        } else {
+            imported.levels.push(state.record(None, trade.execution)?);
+        }
+    }
+    Ok(imported)
+}
+
+pub(crate) fn csv_error(e: csv::Error) -> Error {
+    let message = match e.position() {
+        Some(position) => format!("line {}: {}", position.line(), e),
+        None => e.to_string(),
+    };
+    Error::parse(message)
+}
+
+/// Parse a number, ignoring thousands separators and a trailing unit such
+/// as `BTC` or a leading currency sign
+pub(crate) fn parse_number(text: &str, decimal_comma: bool) -> Result<f64> {
+    let digits = text
+        .trim_start_matches(|c: char| !c.is_ascii_digit() && c != '-' && c != '.')
+        .trim_end_matches(|c: char| c.is_alphabetic() || c.is_whitespace());
+    let normalized = if decimal_comma {
+        digits.replace('.', "").replace(',', ".")
+    } else {
+        digits.replace(',', "")
+    };
+    normalized
+        .parse()
+        .map_err(|_| Error::parse(format!("Invalid number '{}'", text)))
+}
+
+/// Trailing unit of an amount such as `60BTC`, if it has one
+fn unit(text: &str) -> Option<&str> {
+    let text = text.trim_end();
+    let number = text.trim_end_matches(|c: char| c.is_alphabetic());
+    Some(&text[number.len()..]).filter(|unit| !unit.is_empty())
+}
+
+/// Parse a fee into the quote currency. Fees without a unit are taken as
+/// quote currency. A fee in the base asset, the unit of `quantity`, is
+/// converted at the trade price; a fee in any other asset, such as BNB on
+/// Binance, is rejected since the export has no rate for it.
+fn parse_fee(
+    text: &str,
+    price: f64,
+    quantity: &str,
+    symbol: Option<&str>,
+    decimal_comma: bool,
+) -> Result<f64> {
+    let fee = parse_number(text, decimal_comma)?;
+    let Some(asset) = unit(text) else {
+        return Ok(fee);
+    };
+    let base = unit(quantity);
+    if base.is_some_and(|base| base.eq_ignore_ascii_case(asset)) {
+        return Ok(fee * price);
+    }
+
+    // The quote currency is the rest of the pair after the base asset
+    let quote = base
+        .zip(symbol)
+        .and_then(|(base, symbol)| symbol.strip_prefix(base))
+        .map(|quote| quote.trim_start_matches(['-', '/', '_']));
+    match quote {
+        Some(quote) if !quote.eq_ignore_ascii_case(asset) => Err(Error::parse(format!(
+            "Fee '{}' is in {}, not the quote currency {}; convert it before importing",
+            text, asset, quote
+        ))),
+        _ => Ok(fee),
+    }
+}
+
+fn parse_side(text: &str) -> Result<Side> {
+    match text.to_ascii_lowercase().as_str() {
+        "buy" | "b" => Ok(Side::Buy),
+        "sell" | "s" => Ok(Side::Sell),
+        _ => Err(Error::parse(format!(
+            "Invalid side '{}'; expected buy or sell",
+            text
+        ))),
+    }
+}
+
+pub(crate) fn parse_timestamp(text: &str, format: Option<&str>) -> Result<DateTime<Utc>> {
+    let invalid = || format!("Invalid timestamp '{}'", text);
+    if let Some(format) = format {
+        return NaiveDateTime::parse_from_str(text, format)
+            .map(|t| t.and_utc())
+            .map_err(|e| Error::parse(format!("{} for format '{}': {}", invalid(), format, e)));
+    }
+    DateTime::parse_from_rfc3339(text)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").map(|t| t.and_utc())
+        })
+        .map_err(|_| Error::parse(invalid()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::PositionMode;
+
+    fn state() -> GridState {
+        let config = Config::test(&[1.0, 2.0, 3.0], PositionMode::Fixed, 100.0, None);
+        GridState::new(config, None).unwrap()
+    }
+
+    fn fixture(layout: Layout, name: &str) -> Vec<Trade> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/trades")
+            .join(name);
+        read_file(path, &layout.mapping()).unwrap()
+    }
+
+    #[test]
+    fn test_builtin_layouts() {
+        for (layout, name) in [
+            (Layout::Binance, "binance.csv"),
+            (Layout::Coinbase, "coinbase.csv"),
+            (Layout::Kraken, "kraken.csv"),
+        ] {
+            let trades = fixture(layout, name);
+            let buys: Vec<_> = trades
+                .iter()
+                .filter(|t| t.side == Side::Buy && !t.symbol.as_deref().unwrap().starts_with("ETH"))
+                .collect();
+            assert_eq!(buys.len(), 2, "{:?}", layout);
+            // Sorted oldest first, whatever order the export uses
+            assert_eq!(buys[0].execution.price, 99.02, "{:?}", layout);
+            assert_eq!(buys[0].execution.quantity, 100.0, "{:?}", layout);
+            assert_eq!(buys[1].execution.quantity, 60.0, "{:?}", layout);
+            assert_eq!(
+                buys[0]
+                    .execution
+                    .timestamp
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                "2024-05-01 12:15:03"
+            );
+        }
+
+        let binance = fixture(Layout::Binance, "binance.csv");
+        assert_eq!(binance[0].side, Side::Sell);
+        assert_eq!(binance[1].execution.fee, 9.902);
+        // A fee in the base asset is converted at the trade price
+        assert!((binance[2].execution.fee - 0.0015 * 3012.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fee_in_other_asset() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/trades/binance_bnb_fee.csv");
+        let err = read_file(path, &Layout::Binance.mapping()).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("line 3: Fee '0.0001BNB' is in BNB, not the quote currency USDT"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_custom_mapping() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/trades");
+        let mapping = ColumnMapping::from_file(dir.join("custom_mapping.toml")).unwrap();
+        assert_eq!(mapping.delimiter, ';');
+        let trades = read_file(dir.join("custom.csv"), &mapping).unwrap();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].execution.price, 99.02);
+        assert_eq!(trades[1].execution.fee, 0.3);
+        assert_eq!(trades[0].side, Side::Buy);
+    }
+
+    #[test]
+    fn test_import_into_state() {
+        let mut state = state();
+        let trades = fixture(Layout::Binance, "binance.csv");
+        let imported = import(&mut state, trades, Some("BTCUSDT")).unwrap();
+        assert_eq!(imported.levels, vec![1, 2]);
+        assert_eq!(imported.sells, 1);
+        assert_eq!(imported.other_symbols, 1);
+        assert_eq!(state.calculator().current_position(), 160.0);
+    }
+
+    #[test]
+    fn test_errors_name_the_line() {
+        let mapping = Layout::Kraken.mapping();
+        let csv = "time,price,vol,fee,type,pair\n2024-05-01 12:00:00,99,10,0,buy,XBTUSD\n2024-05-01 13:00:00,abc,10,0,buy,XBTUSD\n";
+        let err = read_trades(csv.as_bytes(), &mapping).unwrap_err();
+        assert!(
+            err.to_string().contains("line 3: Invalid number 'abc'"),
+            "{}",
+            err
+        );
+
+        let err = read_trades("price,vol\n".as_bytes(), &mapping).unwrap_err();
+        assert!(
+            err.to_string().contains("Column 'time' not found"),
+            "{}",
+            err
+        );
+    }
+}
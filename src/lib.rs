@@ -1,5 +1,21 @@
+pub mod alert;
+pub mod chart;
 pub mod config;
+pub mod diagnostics;
+pub mod error;
+pub mod exchange;
+pub mod feed;
+pub mod fix;
 pub mod grid_calculator;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod import;
+pub mod orders;
+pub mod portfolio;
+pub mod server;
+pub mod state;
+pub mod watch;
 
-pub use grid_calculator::{GridCalculator, GridType, PositionMode, GridResult};
-pub use config::{Config, Strategy};
\ No newline at end of file
+pub use grid_calculator::{Execution, GridCalculator, GridType, PositionMode, GridResult};
+pub use config::{Config, Strategy};
+pub use error::{Error, Result};
\ No newline at end of file
@@ -1,12 +1,39 @@
-use clap::{Parser, Subcommand, ValueEnum};
-use grid_calculator::{Config, GridCalculator, GridType, PositionMode, Strategy};
-use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use grid_calculator::alert::Alerter;
+use grid_calculator::diagnostics::{self, Diagnostic};
+use grid_calculator::config::format::Format;
+use grid_calculator::config::include::{self, Loaded};
+use grid_calculator::config::overrides::Override;
+use grid_calculator::config::resolve::{ResolvedStrategy, Source};
+use grid_calculator::config::schema;
+use grid_calculator::config::{lint, migrate, BaseConfig, GridConfig, PositionConfig};
+use grid_calculator::exchange::MockExchange;
+use grid_calculator::feed::{self, FeedSource, LiveFeed, SyntheticFeed};
+use grid_calculator::fix::{self, FixSession};
+use grid_calculator::import::{self as trades, ColumnMapping, Layout};
+use grid_calculator::orders::{self, OrderFormat, OrderOptions};
+use grid_calculator::portfolio::PortfolioReport;
+use grid_calculator::server::Server;
+#[cfg(feature = "grpc")]
+use grid_calculator::grpc;
+use grid_calculator::state::{GridState, LadderLevel, LevelStatus};
+use grid_calculator::watch::{WatchUpdate, Watcher};
+use grid_calculator::{chart, Config, Execution, GridCalculator, GridType, PositionMode};
+use std::path::{Path, PathBuf};
+
+mod table;
+mod tui;
+mod wizard;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Format of the config file; detected from its extension if omitted
+    #[arg(long, global = true, value_enum)]
+    config_format: Option<Format>,
 }
 
 #[derive(Subcommand)]
@@ -19,7 +46,7 @@ enum Commands {
 
         /// Grid type
         #[arg(short, long, value_enum)]
-        grid_type: GridTypeArg,
+        grid_type: GridType,
 
         /// Grid percentages (comma-separated, e.g., "1,2,3,5")
         #[arg(short = 'l', long)]
@@ -27,145 +54,849 @@ enum Commands {
 
         /// Position sizing mode
         #[arg(short = 'm', long, value_enum)]
-        mode: PositionModeArg,
+        mode: PositionMode,
 
         /// Initial position size
         #[arg(short = 's', long, default_value = "100.0")]
         size: f64,
 
-        /// Multiplier for position sizing (used in multiplier modes)
-        #[arg(short = 'x', long, default_value = "1.0")]
-        multiplier: f64,
+        /// Multiplier for position sizing (used in multiplier modes, defaults to 1.0)
+        #[arg(short = 'x', long)]
+        multiplier: Option<f64>,
+
+        /// Plot the grid ladder as a chart below the table
+        #[arg(long)]
+        chart: bool,
     },
     
-    /// Run calculation from a TOML config file
+    /// Run calculation from a config file
     FromConfig {
-        /// Path to TOML configuration file
+        /// Path to configuration file (TOML, JSON or YAML) or directory
         #[arg(short, long)]
         config: PathBuf,
         
         /// Strategy name to use (optional, uses main config if not specified)
         #[arg(short, long)]
         strategy: Option<String>,
+
+        /// Plot the grid ladder as a chart below the table
+        #[arg(long)]
+        chart: bool,
+
+        /// Override a config value, e.g. "base.initial_price=123". Applied
+        /// after GRID_<TABLE>__<KEY> environment variables; may be repeated
+        #[arg(long = "set", value_name = "PATH=VALUE")]
+        overrides: Vec<Override>,
     },
     
     /// List all strategies in a config file
     ListStrategies {
+        /// Path to configuration file (TOML, JSON or YAML) or directory
+        #[arg(short, long)]
+        config: PathBuf,
+    },
+
+    /// Check a config file and report every problem found
+    Validate {
+        /// Path to configuration file (TOML, JSON or YAML) or directory
+        #[arg(short, long)]
+        config: PathBuf,
+    },
+
+    /// Create a new TOML config file step by step
+    Init {
+        /// Path of the TOML configuration file to write
+        #[arg(short, long, default_value = "config.toml")]
+        output: PathBuf,
+
+        /// Build the config from flags and defaults without prompting
+        #[arg(long)]
+        non_interactive: bool,
+
+        /// Overwrite the output file if it already exists
+        #[arg(short, long)]
+        force: bool,
+
+        /// Initial price
+        #[arg(short, long)]
+        price: Option<f64>,
+
+        /// Grid type
+        #[arg(short, long, value_enum)]
+        grid_type: Option<GridType>,
+
+        /// Grid percentages (comma-separated, e.g., "1,2,3,5")
+        #[arg(short = 'l', long)]
+        levels: Option<String>,
+
+        /// Position sizing mode
+        #[arg(short = 'm', long, value_enum)]
+        mode: Option<PositionMode>,
+
+        /// Initial position size
+        #[arg(short = 's', long)]
+        size: Option<f64>,
+
+        /// Multiplier for position sizing (used in multiplier modes)
+        #[arg(short = 'x', long)]
+        multiplier: Option<f64>,
+    },
+
+    /// Convert a config between TOML, JSON and YAML
+    Convert {
+        /// Config file to read; its format comes from --config-format or its extension
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Path of the converted config
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Format to write; detected from the output extension if omitted
+        #[arg(long, value_enum)]
+        to: Option<Format>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Upgrade config files written for an older version, keeping a backup
+    Migrate {
+        /// Config file to upgrade, or a directory of config files
+        #[arg(short, long)]
+        config: PathBuf,
+    },
+
+    /// Print a JSON Schema describing the config format
+    Schema {
+        /// Write the schema to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Write the ladder as limit orders for placing on an exchange
+    ExportOrders {
+        #[command(flatten)]
+        orders: OrderArgs,
+
+        /// Order file layout
+        #[arg(long, value_enum, default_value = "csv")]
+        format: OrderFormat,
+
+        /// Write the orders to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Write the ladder as FIX 4.4 NewOrderSingle messages
+    ExportFix {
+        #[command(flatten)]
+        orders: OrderArgs,
+
+        /// SenderCompID of the session
+        #[arg(long)]
+        sender_comp_id: String,
+
+        /// TargetCompID of the session
+        #[arg(long)]
+        target_comp_id: String,
+
+        /// Account to book the orders to
+        #[arg(long)]
+        account: Option<String>,
+
+        /// MsgSeqNum of the first message
+        #[arg(long, default_value = "1")]
+        seq_num: u64,
+
+        /// Show fields separated by '|' instead of SOH; checksums still cover SOH
+        #[arg(long)]
+        readable: bool,
+
+        /// Write the messages to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Paper trade the grid on a mock exchange against a price feed
+    PaperTrade {
+        #[command(flatten)]
+        orders: OrderArgs,
+
+        /// CSV price feed with timestamp and price columns
+        #[arg(long, required_unless_present = "synthetic", conflicts_with = "synthetic")]
+        prices: Option<PathBuf>,
+
+        /// Replay a random walk of this many ticks from the initial price instead
+        #[arg(long)]
+        synthetic: Option<usize>,
+
+        /// Largest move per synthetic tick, in percent
+        #[arg(long, default_value_t = 1.0)]
+        volatility: f64,
+
+        /// Trend of the synthetic walk, in percent per tick
+        #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+        drift: f64,
+
+        /// Seed of the synthetic walk; the same seed replays the same prices
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+
+        /// Fee charged on each fill, as a fraction of its value (0.001 = 0.1%)
+        #[arg(long, default_value_t = 0.0)]
+        fee_rate: f64,
+
+        /// Save the grid state after the run, for `status` and `reconcile`
+        #[arg(long)]
+        save_state: Option<PathBuf>,
+    },
+
+    /// Follow a live price feed against the grid
+    Watch {
+        /// Path to configuration file (TOML, JSON or YAML) or directory
+        #[arg(short, long, required_unless_present = "state", conflicts_with = "state")]
+        config: Option<PathBuf>,
+
+        /// Strategy name to watch (optional, uses main config if not specified)
+        #[arg(short, long, requires = "config")]
+        strategy: Option<String>,
+
+        /// Watch a tracked grid, with its fills, instead
+        #[arg(long)]
+        state: Option<PathBuf>,
+
+        /// Read prices from a file, waiting for lines appended to it (default: stdin)
+        #[arg(long, conflicts_with = "tcp")]
+        file: Option<PathBuf>,
+
+        /// Stop at the end of --file instead of waiting for more lines
+        #[arg(long, requires = "file")]
+        no_follow: bool,
+
+        /// Read prices from a TCP server sending one per line, e.g. 127.0.0.1:9000
+        #[arg(long)]
+        tcp: Option<String>,
+
+        /// Print one JSON object per price instead of a line of text
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show how a portfolio's capital is taken when every ladder fills at once
+    Portfolio {
+        /// Path to configuration file (TOML, JSON or YAML) or directory
+        #[arg(short, long)]
+        config: PathBuf,
+
+        /// Print the report as JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Start tracking a grid live, saving its state to a file
+    Start {
+        /// Path to configuration file (TOML, JSON or YAML) or directory
+        #[arg(short, long)]
+        config: PathBuf,
+
+        /// Strategy name to track (optional, uses main config if not specified)
+        #[arg(short, long)]
+        strategy: Option<String>,
+
+        /// Path of the state file to create
+        #[arg(long, default_value = DEFAULT_STATE)]
+        state: PathBuf,
+
+        /// Overwrite the state file if it already exists
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Record that a grid level filled and save the updated state
+    Fill {
+        /// Level that filled, counting from 1 in the order of grid.levels
+        #[arg(short, long)]
+        level: usize,
+
+        /// Path of the state file
+        #[arg(long, default_value = DEFAULT_STATE)]
+        state: PathBuf,
+    },
+
+    /// Record an actual execution and match it to a grid level
+    Record {
+        /// Execution price
+        #[arg(short, long)]
+        price: f64,
+
+        /// Executed quantity
+        #[arg(short, long)]
+        quantity: f64,
+
+        /// Fee paid, in the same currency as the cost
+        #[arg(short, long, default_value = "0.0")]
+        fee: f64,
+
+        /// Execution time (RFC 3339, e.g. "2024-05-01T12:00:00Z"); defaults to now
+        #[arg(short, long)]
+        time: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Level the execution belongs to; matched by closest planned price if omitted
+        #[arg(short, long)]
+        level: Option<usize>,
+
+        /// Path of the state file
+        #[arg(long, default_value = DEFAULT_STATE)]
+        state: PathBuf,
+    },
+
+    /// Record the buys from an exchange trade history CSV export
+    ImportTrades {
+        /// CSV file to import
+        #[arg(short, long)]
+        file: PathBuf,
+
+        /// Built-in column layout of the export
+        #[arg(long, value_enum, required_unless_present = "mapping", conflicts_with = "mapping")]
+        layout: Option<Layout>,
+
+        /// TOML file mapping trade fields to CSV columns, for other exports
+        #[arg(long)]
+        mapping: Option<PathBuf>,
+
+        /// Only import trades for this symbol, e.g. "BTCUSDT"
+        #[arg(long)]
+        symbol: Option<String>,
+
+        /// Path of the state file
+        #[arg(long, default_value = DEFAULT_STATE)]
+        state: PathBuf,
+    },
+
+    /// Compare recorded executions with the planned ladder
+    Reconcile {
+        /// Path of the state file
+        #[arg(long, default_value = DEFAULT_STATE)]
+        state: PathBuf,
+    },
+
+    /// Show the tracked position and the levels still to fill
+    Status {
+        /// Path of the state file
+        #[arg(long, default_value = DEFAULT_STATE)]
+        state: PathBuf,
+    },
+
+    /// Serve the calculator as a JSON REST API
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        bind: String,
+
+        /// Config listed by GET /strategies and calculated when a request has no body
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+    },
+
+    /// Serve the calculator as a gRPC service
+    #[cfg(feature = "grpc")]
+    ServeGrpc {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        bind: String,
+    },
+
+    /// Edit a TOML config interactively with a live preview
+    Tui {
         /// Path to TOML configuration file
         #[arg(short, long)]
         config: PathBuf,
     },
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
-enum GridTypeArg {
-    /// Fixed price grid (relative to initial price)
-    Fixed,
-    /// Average price grid (relative to average price)
-    Average,
-}
+const DEFAULT_STATE: &str = "grid-state.json";
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
-enum PositionModeArg {
-    /// Fixed size for each grid level
-    Fixed,
-    /// Multiple of current position
-    CurrentMultiple,
-    /// Multiple of last increment
-    IncrementMultiple,
-}
+/// Where an order export takes its ladder from and how orders are built
+#[derive(Args)]
+struct OrderArgs {
+    /// Path to configuration file (TOML, JSON or YAML) or directory
+    #[arg(short, long, required_unless_present = "state", conflicts_with = "state")]
+    config: Option<PathBuf>,
 
-impl From<GridTypeArg> for GridType {
-    fn from(arg: GridTypeArg) -> Self {
-        match arg {
-            GridTypeArg::Fixed => GridType::Fixed,
-            GridTypeArg::Average => GridType::Average,
-        }
-    }
-}
+    /// Strategy name to export (optional, uses main config if not specified)
+    #[arg(short, long, requires = "config")]
+    strategy: Option<String>,
 
-impl From<PositionModeArg> for PositionMode {
-    fn from(arg: PositionModeArg) -> Self {
-        match arg {
-            PositionModeArg::Fixed => PositionMode::Fixed,
-            PositionModeArg::CurrentMultiple => PositionMode::CurrentMultiple,
-            PositionModeArg::IncrementMultiple => PositionMode::IncrementMultiple,
-        }
-    }
-}
+    /// Export the levels still to fill in a state file instead
+    #[arg(long)]
+    state: Option<PathBuf>,
 
-fn parse_grid_type(grid_type: &str) -> Result<GridType, String> {
-    match grid_type {
-        "fixed" => Ok(GridType::Fixed),
-        "average" => Ok(GridType::Average),
-        _ => Err(format!("Invalid grid type: {}", grid_type)),
-    }
+    /// Symbol as the exchange names it, e.g. "BTCUSDT"
+    #[arg(long)]
+    symbol: String,
+
+    /// Price increment; prices are rounded down to it
+    #[arg(long)]
+    tick_size: Option<f64>,
+
+    /// Quantity increment; quantities are rounded down to it
+    #[arg(long)]
+    lot_size: Option<f64>,
+
+    /// Client order IDs are this prefix followed by the level number
+    #[arg(long, default_value = "grid")]
+    id_prefix: String,
 }
 
-fn parse_position_mode(mode: &str) -> Result<PositionMode, String> {
-    match mode {
-        "fixed" => Ok(PositionMode::Fixed),
-        "current-multiple" => Ok(PositionMode::CurrentMultiple),
-        "increment-multiple" => Ok(PositionMode::IncrementMultiple),
-        _ => Err(format!("Invalid position mode: {}", mode)),
-    }
+fn parse_levels(levels: &str) -> Result<Vec<f64>, String> {
+    levels
+        .split(',')
+        .map(|s| {
+            s.trim()
+                .parse()
+                .map_err(|_| format!("Invalid grid level: '{}'", s.trim()))
+        })
+        .collect()
 }
 
-fn print_calculation_header(price: f64, grid_type: &str, mode: &str, size: f64, multiplier: Option<f64>) {
+fn print_calculation_header(price: f64, grid_type: GridType, mode: PositionMode, size: f64, multiplier: Option<f64>) {
     println!("\n=== Grid Trading Calculator ===");
     println!("Initial Price: ${:.2}", price);
     println!("Grid Type: {}", grid_type);
     println!("Position Mode: {}", mode);
     println!("Base Size: {:.2}", size);
     if let Some(mult) = multiplier {
-        if mode.contains("multiple") {
+        if mode.uses_multiplier() {
             println!("Multiplier: {:.2}x", mult);
         }
     }
-    println!("\n{:-<60}", "");
-    println!("{:<5} {:>10} {:>10} {:>10} {:>10} {:>10}", 
-             "Grid", "Level %", "Price", "Size", "Total", "Avg Price");
-    println!("{:-<60}", "");
+    println!("\n{}", table::rule());
+    println!("{}", table::ladder_header());
+    println!("{}", table::rule());
 }
 
 fn print_calculation_results(calculator: &mut GridCalculator, levels: &[f64]) {
     for (i, &level) in levels.iter().enumerate() {
         let result = calculator.calculate_grid(level);
-        println!("{:<5} {:>10.2}% {:>10.2} {:>10.2} {:>10.2} {:>10.2}",
-                 i + 1,
-                 level,
-                 result.grid_price,
-                 result.position_size,
-                 result.total_position,
-                 result.average_price);
-    }
-    println!("{:-<60}", "");
+        println!("{}", table::ladder_row(i + 1, level, &result));
+    }
+    println!("{}", table::rule());
+}
+
+fn print_chart(calculator: &GridCalculator) {
+    let width = terminal_size::terminal_size()
+        .map(|(terminal_size::Width(w), _)| w as usize)
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(80);
+    println!();
+    print!("{}", chart::render(calculator.get_history(), width));
+}
+
+fn print_diagnostics(path: Option<&Path>, diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        match path {
+            Some(path) => eprintln!("{}:{}", path.display(), diagnostic),
+            None => eprintln!("{}", diagnostic),
+        }
+    }
+}
+
+/// Print lint findings for `config` and stop if any lint is denied
+fn check_lints(config: &Config, path: Option<&Path>) {
+    let mut findings = lint::lint(config);
+    match path {
+        Some(path) => locate_and_print(path, &mut findings),
+        None => print_diagnostics(None, &findings),
+    }
+    if diagnostics::has_errors(&findings) {
+        exit_on_denied_lints();
+    }
+}
+
+/// Like `check_lints`, reporting each finding against the file it is in
+fn check_loaded_lints(loaded: &Loaded) {
+    let mut denied = false;
+    for (file, mut findings) in loaded.by_file(lint::lint(&loaded.config)) {
+        locate_and_print(&file, &mut findings);
+        denied |= diagnostics::has_errors(&findings);
+    }
+    if denied {
+        exit_on_denied_lints();
+    }
+}
+
+fn exit_on_denied_lints() -> ! {
+    eprintln!("Error: config violates denied lints");
+    std::process::exit(1);
+}
+
+fn locate_and_print(path: &Path, diagnostics: &mut [Diagnostic]) {
+    if let Ok(source) = std::fs::read_to_string(path) {
+        diagnostics::locate(diagnostics, &source);
+    }
+    print_diagnostics(Some(path), diagnostics);
+}
+
+/// Describe where an inherited strategy value came from
+fn origin(resolved: &ResolvedStrategy, path: &str) -> String {
+    match resolved.source_of(path) {
+        Some(Source::Strategy(name)) if *name == resolved.name => String::new(),
+        Some(source) => format!(" (from {})", source),
+        None => String::new(),
+    }
+}
+
+/// Load a config file or directory with everything it includes, then
+/// apply `overrides` on top
+fn load_config(path: &Path, format: Option<Format>, overrides: &[Override]) -> Config {
+    let mut loaded = match include::load(path, format).and_then(|l| l.check_duplicates().map(|_| l)) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Error loading config file: {}", e);
+            std::process::exit(1);
+        }
+    };
+    loaded.config = match loaded.config.with_overrides(overrides) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error applying config overrides: {}", e);
+            std::process::exit(1);
+        }
+    };
+    check_loaded_lints(&loaded);
+    loaded.config
+}
+
+/// Diagnostics for a config, grouped by the file each one belongs to.
+/// Single files keep parse errors with their location; configs with
+/// includes are merged first.
+fn validation_groups(
+    path: &Path,
+    format: Option<Format>,
+) -> grid_calculator::Result<Vec<(PathBuf, Vec<Diagnostic>)>> {
+    let format = format.unwrap_or_else(|| Format::detect(path));
+    if path.is_file() && format == Format::Toml {
+        let contents = std::fs::read_to_string(path).map_err(|source| grid_calculator::Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let includes = toml::from_str::<Config>(&contents).is_ok_and(|c| c.include.is_some());
+        if !includes {
+            return Ok(vec![(path.to_path_buf(), diagnostics::check_toml(&contents))]);
+        }
+    }
+
+    let loaded = include::load(path, Some(format))?;
+    Ok(loaded.by_file(loaded.config.diagnostics()))
+}
+
+fn load_state(path: &Path) -> GridState {
+    match GridState::from_file(path) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error loading state file: {}", e);
+            std::process::exit(1);
+        }
+    }
 }
 
-fn run_calculation(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+fn save_state(state: &GridState, path: &Path) {
+    if let Err(e) = state.to_file(path) {
+        eprintln!("Error writing state file: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Stop if `output` exists and may not be overwritten
+fn check_output(output: Option<&Path>, force: bool) {
+    if let Some(path) = output.filter(|p| p.exists() && !force) {
+        eprintln!("Error: {} already exists (use --force to overwrite)", path.display());
+        std::process::exit(1);
+    }
+}
+
+/// Write `text` to `output`, or to stdout if no file is given
+fn write_output(output: Option<&Path>, text: &str, what: &str) {
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, text) {
+                eprintln!("Error writing output: {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+            println!("{} written to {}", what, path.display());
+        }
+        None => print!("{}", text),
+    }
+}
+
+/// The config a grid tracks: the main config, or one of its strategies.
+/// Strategies are watched with the main config's alerts.
+fn tracked_config(cfg: &Config, strategy: Option<&str>) -> grid_calculator::Result<Config> {
+    match strategy {
+        Some(name) => cfg
+            .strategy(name)
+            .and_then(|s| s.to_config(cfg))
+            .map(|c| Config {
+                alerts: cfg.alerts.clone(),
+                ..c
+            }),
+        None => Ok(cfg.clone()),
+    }
+}
+
+/// The grid to work from: a tracked state, or a fresh one for the config
+/// or one of its strategies
+fn grid_state(
+    config: Option<&Path>,
+    strategy: Option<&str>,
+    state: Option<&Path>,
+    format: Option<Format>,
+) -> GridState {
+    let state = match (config, state) {
+        (_, Some(state)) => return load_state(state),
+        (Some(config), None) => {
+            let cfg = load_config(config, format, &Override::from_env());
+            tracked_config(&cfg, strategy)
+                .and_then(|c| GridState::new(c, strategy.map(String::from)))
+        }
+        (None, None) => unreachable!("clap requires --config or --state"),
+    };
+    match state {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error loading grid: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn order_state(args: &OrderArgs, format: Option<Format>) -> GridState {
+    grid_state(args.config.as_deref(), args.strategy.as_deref(), args.state.as_deref(), format)
+}
+
+fn order_options(args: OrderArgs) -> OrderOptions {
+    OrderOptions {
+        tick_size: args.tick_size,
+        lot_size: args.lot_size,
+        id_prefix: args.id_prefix,
+        ..OrderOptions::new(args.symbol)
+    }
+}
+
+fn build_orders(args: OrderArgs, format: Option<Format>) -> (Vec<orders::Order>, OrderOptions) {
+    let ladder = order_state(&args, format).remaining();
+    let options = order_options(args);
+    match orders::build_orders(&ladder, &options) {
+        Ok(orders) => (orders, options),
+        Err(e) => {
+            eprintln!("Error building orders: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_watch_update(update: &WatchUpdate) {
+    for level in update.crossed.iter().filter(|l| update.newly_crossed.contains(&l.level)) {
+        println!(">> Level {} crossed at {:.2} (level price {:.2})", level.level, update.price, level.result.grid_price);
+    }
+    let next = match &update.next {
+        Some(n) => format!("next L{} {:.2} (-{:.2}%)", n.level, n.price, n.distance_percent),
+        None => "no level below".to_string(),
+    };
+    println!("{}  {:>10.2}  {}  crossed {}  position {:.2}  uPnL {:.2}",
+             update.timestamp.format("%Y-%m-%d %H:%M:%S"),
+             update.price,
+             next,
+             update.crossed.len(),
+             update.pnl.position,
+             update.pnl.unrealized);
+}
+
+fn print_paper_trade(exchange: &MockExchange) {
+    println!("\nFills:");
+    if exchange.fills().is_empty() {
+        println!("  (none)");
+    } else {
+        println!("{:-<78}", "");
+        println!("{:<20} {:<5} {:>10} {:>10} {:>10} {:>10} {:>8}",
+                 "Time", "Grid", "Price", "Size", "Market", "Fee", "Order");
+        println!("{:-<78}", "");
+        for f in exchange.fills() {
+            println!("{:<20} {:<5} {:>10.2} {:>10.4} {:>10.2} {:>10.4} {:>8}",
+                     f.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                     f.order.level,
+                     f.order.price,
+                     f.order.quantity,
+                     f.market_price,
+                     f.fee,
+                     f.order.client_order_id);
+        }
+    }
+
+    println!("\nOpen Orders:");
+    if exchange.book().is_empty() {
+        println!("  (none)");
+    } else {
+        println!("{:-<40}", "");
+        println!("{:<5} {:>10} {:>10} {:>10}", "Grid", "Price", "Size", "Order");
+        println!("{:-<40}", "");
+        for o in exchange.book() {
+            println!("{:<5} {:>10.2} {:>10.4} {:>10}", o.level, o.price, o.quantity, o.client_order_id);
+        }
+    }
+
+    if let Some(price) = exchange.last_price() {
+        let pnl = exchange.pnl(price);
+        println!("\n=== PnL at ${:.2} ===", pnl.mark_price);
+        println!("Position: {:.4}", pnl.position);
+        println!("Average Price: {:.2}", pnl.average_price);
+        println!("Cost (incl. fees): {:.2}", pnl.cost);
+        println!("Fees: {:.2}", pnl.fees);
+        println!("Market Value: {:.2}", pnl.market_value);
+        println!("Unrealized PnL: {:.2}", pnl.unrealized);
+    }
+}
+
+fn print_portfolio(report: &PortfolioReport) {
+    println!("\n=== Portfolio ===");
+    println!("Capital: {:.2}", report.capital);
+    println!("{:-<110}", "");
+    println!("{:<12} {:<10} {:>10} {:>6} {:>10} {:>10} {:>10} {:>12} {:>9} {:>12}",
+             "Symbol", "Strategy", "Initial", "Levels", "Position", "Avg Price", "Lowest", "Required", "Capital", "Worst Loss");
+    println!("{:-<110}", "");
+    for a in &report.assets {
+        println!("{:<12} {:<10} {:>10.2} {:>6} {:>10.4} {:>10.2} {:>10.2} {:>12.2} {:>8.2}% {:>12.2}",
+                 a.symbol,
+                 a.strategy,
+                 a.initial_price,
+                 a.levels,
+                 a.position,
+                 a.average_price,
+                 a.lowest_price,
+                 a.required,
+                 a.capital_percent,
+                 a.worst_case_loss);
+    }
+    println!("{:-<110}", "");
+
+    println!("Required: {:.2}", report.required);
+    if report.free >= 0.0 {
+        println!("Free: {:.2}", report.free);
+    } else {
+        println!("Shortfall: {:.2}", -report.free);
+    }
+    println!("Worst-Case Loss: {:.2} ({:.2}% of capital)", report.worst_case_loss, report.worst_case_loss_percent);
+    if report.scale < 1.0 {
+        println!("\nWarning: the ladders need more than the capital; scale position sizes by {:.4} to fit", report.scale);
+    }
+}
+
+fn print_ladder(title: &str, levels: &[LadderLevel]) {
+    println!("\n{}:", title);
+    if levels.is_empty() {
+        println!("  (none)");
+        return;
+    }
+    println!("{}", table::rule());
+    println!("{}", table::ladder_header());
+    println!("{}", table::rule());
+    for l in levels {
+        println!("{}", table::ladder_row(l.level, l.percent, &l.result));
+    }
+    println!("{}", table::rule());
+}
+
+fn print_state(state: &GridState) {
+    let calculator = state.calculator();
+    println!("\n=== Grid State ===");
+    if let Some(name) = &state.strategy {
+        println!("Strategy: {}", name);
+    }
+    println!("Initial Price: ${:.2}", state.config.base.initial_price);
+    println!("Position: {:.2}", calculator.current_position());
+    println!("Total Cost: {:.2}", calculator.total_cost());
+    println!("Average Price: {:.2}", calculator.average_price());
+    print_ladder("Filled Levels", &state.fills());
+    print_ladder("Remaining Levels", &state.remaining());
+}
+
+fn print_reconciliation(state: &GridState) {
+    let reports = state.reconcile();
+    println!("\n=== Reconciliation ===");
+    println!("{:-<92}", "");
+    println!("{:<5} {:>9} {:>10} {:>10} {:>9} {:>10} {:>10} {:>8}  Status",
+             "Grid", "Level %", "Plan Price", "Exec Price", "Slip %", "Plan Size", "Exec Size", "Fees");
+    println!("{:-<92}", "");
+    for r in &reports {
+        match &r.fill {
+            Some(fill) => println!("{:<5} {:>8.2}% {:>10.2} {:>10.2} {:>8.2}% {:>10.2} {:>10.2} {:>8.2}  {}",
+                                   r.level,
+                                   r.percent,
+                                   r.planned.grid_price,
+                                   fill.executed.grid_price,
+                                   fill.slippage_percent(),
+                                   r.planned.position_size,
+                                   fill.executed.position_size,
+                                   fill.fees,
+                                   r.status.as_str()),
+            None => println!("{:<5} {:>8.2}% {:>10.2} {:>10} {:>9} {:>10.2} {:>10} {:>8}  {}",
+                             r.level,
+                             r.percent,
+                             r.planned.grid_price,
+                             "-",
+                             "-",
+                             r.planned.position_size,
+                             "-",
+                             "-",
+                             r.status.as_str()),
+        }
+    }
+    println!("{:-<92}", "");
+
+    let calculator = state.calculator();
+    println!("Position: {:.2}", calculator.current_position());
+    println!("Total Cost: {:.2}", calculator.total_cost());
+    println!("Average Price: {:.2}", calculator.average_price());
+
+    let levels_with = |status: LevelStatus| -> Vec<String> {
+        reports.iter().filter(|r| r.status == status).map(|r| r.level.to_string()).collect()
+    };
+    for (status, label) in [(LevelStatus::Missing, "Missing levels"), (LevelStatus::OverFilled, "Over-filled levels"), (LevelStatus::Partial, "Partially filled levels")] {
+        let levels = levels_with(status);
+        if !levels.is_empty() {
+            println!("{}: {}", label, levels.join(", "));
+        }
+    }
+}
+
+fn run_calculation(config: &Config, chart: bool) -> grid_calculator::Result<()> {
     config.validate()?;
     
-    let grid_type = parse_grid_type(&config.base.grid_type)?;
-    let position_mode = parse_position_mode(&config.position.mode)?;
-    let multiplier = config.position.multiplier.unwrap_or(1.0);
-    
-    let mut calculator = GridCalculator::new(
-        config.base.initial_price,
-        grid_type,
-        position_mode,
-        config.position.base_size,
-        multiplier,
-    );
+    let mut calculator = config.calculator();
     
     print_calculation_header(
         config.base.initial_price,
-        &config.base.grid_type,
-        &config.position.mode,
+        config.base.grid_type,
+        config.position.mode,
         config.position.base_size,
         config.position.multiplier,
     );
     
     print_calculation_results(&mut calculator, &config.grid.levels);
+
+    if chart {
+        print_chart(&calculator);
+    }
     
     Ok(())
 }
@@ -181,6 +912,7 @@ fn main() {
             mode,
             size,
             multiplier,
+            chart,
         } => {
             let grid_levels: Vec<f64> = levels
                 .split(',')
@@ -191,63 +923,76 @@ fn main() {
                 eprintln!("Error: No valid grid levels provided");
                 std::process::exit(1);
             }
+            if let Err(e) = grid_calculator::config::validate_multiplier(mode, Some(multiplier.unwrap_or(1.0))) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
 
-            let mut calculator = GridCalculator::new(
-                price,
-                grid_type.into(),
-                mode.into(),
-                size,
-                multiplier,
-            );
-
-            print_calculation_header(
-                price,
-                &format!("{:?}", grid_type),
-                &format!("{:?}", mode),
-                size,
-                if matches!(mode, PositionModeArg::CurrentMultiple | PositionModeArg::IncrementMultiple) {
-                    Some(multiplier)
-                } else {
-                    None
+            let cfg = Config {
+                base: BaseConfig {
+                    initial_price: price,
+                    grid_type,
+                },
+                grid: GridConfig {
+                    levels: grid_levels.clone(),
+                },
+                position: PositionConfig {
+                    mode,
+                    base_size: size,
+                    multiplier,
                 },
-            );
+                version: migrate::CURRENT_VERSION,
+                include: None,
+                strategies: None,
+                lints: None,
+                alerts: None,
+                portfolio: None,
+            };
+            check_lints(&cfg, None);
+
+            let mut calculator = cfg.calculator();
+
+            print_calculation_header(price, grid_type, mode, size, Some(multiplier.unwrap_or(1.0)));
             
             print_calculation_results(&mut calculator, &grid_levels);
+
+            if chart {
+                print_chart(&calculator);
+            }
         }
         
-        Commands::FromConfig { config, strategy } => {
-            let cfg = match Config::from_file(&config) {
-                Ok(c) => c,
-                Err(e) => {
-                    eprintln!("Error loading config file: {}", e);
-                    std::process::exit(1);
-                }
-            };
+        Commands::FromConfig { config, strategy, chart, overrides } => {
+            let mut layers = Override::from_env();
+            layers.extend(overrides);
+            let cfg = load_config(&config, cli.config_format, &layers);
             
             if let Some(strategy_name) = strategy {
                 // Use specific strategy
-                if let Some(strategies) = &cfg.strategies {
-                    if let Some(strat) = strategies.iter().find(|s| s.name == strategy_name) {
-                        if let Err(e) = strat.validate() {
-                            eprintln!("Error validating strategy: {}", e);
-                            std::process::exit(1);
-                        }
-                        let strategy_config = strat.to_config();
-                        if let Err(e) = run_calculation(&strategy_config) {
-                            eprintln!("Error running calculation: {}", e);
-                            std::process::exit(1);
-                        }
-                    } else {
-                        eprintln!("Strategy '{}' not found in config", strategy_name);
+                let strat = match cfg.strategy(&strategy_name) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
                         std::process::exit(1);
                     }
-                } else {
-                    eprintln!("No strategies defined in config file");
+                };
+                if let Err(e) = strat.validate(&cfg) {
+                    eprintln!("Error validating strategy: {}", e);
+                    std::process::exit(1);
+                }
+                let strategy_config = match strat.to_config(&cfg) {
+                    Ok(c) => c,
+                    Err(e) => {
+                        eprintln!("Error resolving strategy: {}", e);
+                        std::process::exit(1);
+                    }
+                };
+                if let Err(e) = run_calculation(&strategy_config, chart) {
+                    eprintln!("Error running calculation: {}", e);
                     std::process::exit(1);
                 }
             } else {
                 // Use main config
-                if let Err(e) = run_calculation(&cfg) {
+                if let Err(e) = run_calculation(&cfg, chart) {
                     eprintln!("Error running calculation: {}", e);
                     std::process::exit(1);
                 }
@@ -255,13 +1000,7 @@ fn main() {
         }
         
         Commands::ListStrategies { config } => {
-            let cfg = match Config::from_file(&config) {
-                Ok(c) => c,
-                Err(e) => {
-                    eprintln!("Error loading config file: {}", e);
-                    std::process::exit(1);
-                }
-            };
+            let cfg = load_config(&config, cli.config_format, &Override::from_env());
             
             println!("\n=== Available Strategies ===\n");
             
@@ -276,16 +1015,540 @@ fn main() {
                 println!("\nNamed Strategies:");
                 for strat in strategies {
                     println!("\n  Strategy: '{}'", strat.name);
-                    println!("    Grid Type: {}", strat.grid_type);
-                    println!("    Position Mode: {}", strat.position_mode);
-                    println!("    Levels: {:?}", strat.levels);
-                    if let Some(mult) = strat.multiplier {
-                        println!("    Multiplier: {:.2}x", mult);
+                    if let Some(parent) = &strat.extends {
+                        println!("    Extends: {}", parent);
+                    }
+                    let resolved = match strat.resolve(&cfg) {
+                        Ok(r) => r,
+                        Err(e) => {
+                            println!("    Error: {}", e);
+                            continue;
+                        }
+                    };
+                    let c = &resolved.config;
+                    println!("    Initial Price: {:.2}{}", c.base.initial_price, origin(&resolved, "base.initial_price"));
+                    println!("    Grid Type: {}{}", c.base.grid_type, origin(&resolved, "base.grid_type"));
+                    println!("    Position Mode: {}{}", c.position.mode, origin(&resolved, "position.mode"));
+                    println!("    Levels: {:?}{}", c.grid.levels, origin(&resolved, "grid.levels"));
+                    println!("    Base Size: {:.2}{}", c.position.base_size, origin(&resolved, "position.base_size"));
+                    if let Some(mult) = c.position.multiplier {
+                        println!("    Multiplier: {:.2}x{}", mult, origin(&resolved, "position.multiplier"));
                     }
                 }
             } else {
                 println!("\nNo named strategies defined.");
             }
         }
+
+        Commands::Validate { config } => {
+            let groups = match validation_groups(&config, cli.config_format) {
+                Ok(groups) => groups,
+                Err(e) => {
+                    eprintln!("Error loading config file: {}", e);
+                    std::process::exit(1);
+                }
+            };
+
+            let mut errors = 0;
+            let mut warnings = 0;
+            for (file, mut diagnostics) in groups {
+                locate_and_print(&file, &mut diagnostics);
+                let file_errors = diagnostics
+                    .iter()
+                    .filter(|d| d.severity == diagnostics::Severity::Error)
+                    .count();
+                errors += file_errors;
+                warnings += diagnostics.len() - file_errors;
+            }
+
+            if errors + warnings == 0 {
+                println!("{}: OK", config.display());
+            } else {
+                println!("{}: {} error(s), {} warning(s)", config.display(), errors, warnings);
+            }
+
+            if errors > 0 {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Init {
+            output,
+            non_interactive,
+            force,
+            price,
+            grid_type,
+            levels,
+            mode,
+            size,
+            multiplier,
+        } => {
+            if output.exists() && !force {
+                eprintln!("Error: {} already exists (use --force to overwrite)", output.display());
+                std::process::exit(1);
+            }
+
+            let levels = match levels.as_deref().map(parse_levels).transpose() {
+                Ok(levels) => levels,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let answers = wizard::Answers {
+                price,
+                grid_type,
+                levels,
+                mode,
+                size,
+                multiplier,
+            };
+
+            let cfg = if non_interactive {
+                match answers.into_config() {
+                    Ok(cfg) => {
+                        if let Err(e) = wizard::write_preview(&mut std::io::stdout(), &cfg) {
+                            eprintln!("Error printing preview: {}", e);
+                            std::process::exit(1);
+                        }
+                        cfg
+                    }
+                    Err(e) => {
+                        eprintln!("Error validating config: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                let stdin = std::io::stdin();
+                match wizard::run_interactive(&mut stdin.lock(), &mut std::io::stdout(), answers) {
+                    Ok(Some(cfg)) => cfg,
+                    Ok(None) => {
+                        println!("No config written.");
+                        return;
+                    }
+                    Err(e) => {
+                        eprintln!("Error reading answers: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            };
+
+            if let Err(e) = cfg.to_file(&output) {
+                eprintln!("Error writing config file: {}", e);
+                std::process::exit(1);
+            }
+            println!("Config written to {}", output.display());
+        }
+
+        Commands::Convert {
+            input,
+            output,
+            to,
+            force,
+        } => {
+            if output.exists() && !force {
+                eprintln!("Error: {} already exists (use --force to overwrite)", output.display());
+                std::process::exit(1);
+            }
+
+            let from = cli.config_format.unwrap_or_else(|| Format::detect(&input));
+            let to = to.unwrap_or_else(|| Format::detect(&output));
+            let cfg = match Config::from_file_as(&input, from) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("Error loading config file: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            // The file is converted as written, without includes or overrides
+            check_lints(&cfg, Some(&input));
+            if let Err(e) = cfg.to_file_as(&output, to) {
+                eprintln!("Error writing config file: {}", e);
+                std::process::exit(1);
+            }
+            println!("Converted {} ({}) to {} ({})", input.display(), from, output.display(), to);
+        }
+
+        Commands::Migrate { config } => {
+            let files = if config.is_dir() {
+                match include::config_files(&config) {
+                    Ok(files) => files,
+                    Err(e) => {
+                        eprintln!("Error reading directory: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                vec![config]
+            };
+
+            for file in &files {
+                let format = cli.config_format.unwrap_or_else(|| Format::detect(file));
+                match migrate::migrate_file(file, format) {
+                    Ok(Some(migrated)) => {
+                        println!(
+                            "{}: migrated from version {} to {} (backup: {})",
+                            file.display(),
+                            migrated.from,
+                            migrate::CURRENT_VERSION,
+                            migrated.backup.display()
+                        );
+                        for step in migrated.steps {
+                            println!("  - {}", step);
+                        }
+                    }
+                    Ok(None) => println!("{}: already at version {}", file.display(), migrate::CURRENT_VERSION),
+                    Err(e) => {
+                        eprintln!("Error migrating config file: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+
+            // Strategy libraries have no main config to lint
+            for file in &files {
+                let format = cli.config_format.unwrap_or_else(|| Format::detect(file));
+                if let Ok(cfg) = Config::from_file_as(file, format) {
+                    check_lints(&cfg, Some(file));
+                }
+            }
+        }
+
+        Commands::Schema { output } => {
+            let schema = schema::json_schema();
+            let text = serde_json::to_string_pretty(&schema).expect("schemas serialize to JSON") + "\n";
+            match output {
+                Some(path) => {
+                    if let Err(e) = std::fs::write(&path, text) {
+                        eprintln!("Error writing schema: {}: {}", path.display(), e);
+                        std::process::exit(1);
+                    }
+                    println!("Schema written to {}", path.display());
+                }
+                None => print!("{}", text),
+            }
+        }
+
+        Commands::ExportOrders { orders: args, format, output, force } => {
+            check_output(output.as_deref(), force);
+            let (orders, options) = build_orders(args, cli.config_format);
+            let text = match orders::render(&orders, format, &options) {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("Error exporting orders: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            write_output(output.as_deref(), &text, &format!("Orders ({})", format.as_str()));
+        }
+
+        Commands::PaperTrade {
+            orders: args,
+            prices,
+            synthetic,
+            volatility,
+            drift,
+            seed,
+            fee_rate,
+            save_state: state_path,
+        } => {
+            let state = order_state(&args, cli.config_format);
+            let feed = match (prices, synthetic) {
+                (Some(path), _) => feed::read_price_file(&path),
+                (None, Some(ticks)) => SyntheticFeed {
+                    volatility,
+                    drift,
+                    seed,
+                    ..SyntheticFeed::new(state.config.base.initial_price, ticks, chrono::Utc::now())
+                }
+                .generate(),
+                (None, None) => unreachable!("clap requires --prices or --synthetic"),
+            };
+            let feed = match feed {
+                Ok(f) => f,
+                Err(e) => {
+                    eprintln!("Error reading price feed: {}", e);
+                    std::process::exit(1);
+                }
+            };
+
+            let mut exchange = match MockExchange::new(state, order_options(args), fee_rate) {
+                Ok(e) => e,
+                Err(e) => {
+                    eprintln!("Error building orders: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            if let Err(e) = exchange.replay(&feed) {
+                eprintln!("Error matching orders: {}", e);
+                std::process::exit(1);
+            }
+
+            println!("\n=== Paper Trade ===");
+            println!("Ticks: {}", feed.len());
+            print_paper_trade(&exchange);
+            if let Some(path) = state_path {
+                save_state(exchange.state(), &path);
+                println!("\nState saved to {}", path.display());
+            }
+        }
+
+        Commands::Watch { config, strategy, state, file, no_follow, tcp, json } => {
+            let state = grid_state(config.as_deref(), strategy.as_deref(), state.as_deref(), cli.config_format);
+            let source = match (file, tcp) {
+                (Some(path), _) => FeedSource::File { path, follow: !no_follow },
+                (None, Some(address)) => FeedSource::Tcp(address),
+                (None, None) => FeedSource::Stdin,
+            };
+            let feed = match LiveFeed::open(source) {
+                Ok(f) => f,
+                Err(e) => {
+                    eprintln!("Error opening price feed: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            if !json {
+                println!("Watching {} ({} levels remaining)", feed.source(), state.remaining().len());
+            }
+
+            let mut alerter = state.config.alerts.as_ref().map(Alerter::new);
+            let mut watcher = Watcher::new(state);
+            for tick in feed {
+                let tick = match tick {
+                    Ok(t) => t,
+                    // A garbled line should not stop a live feed
+                    Err(e @ grid_calculator::Error::Parse { .. }) => {
+                        eprintln!("Warning: {}", e);
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("Error reading price feed: {}", e);
+                        std::process::exit(1);
+                    }
+                };
+                let update = watcher.update(&tick);
+                if json {
+                    println!("{}", serde_json::to_string(&update).expect("updates serialize"));
+                } else {
+                    print_watch_update(&update);
+                }
+                for e in alerter.as_mut().map(|a| a.process(&update)).unwrap_or_default() {
+                    eprintln!("Warning: {}", e);
+                }
+            }
+        }
+
+        Commands::Portfolio { config, json } => {
+            let cfg = load_config(&config, cli.config_format, &Override::from_env());
+            let portfolio = match &cfg.portfolio {
+                Some(p) => p,
+                None => {
+                    eprintln!("Error: {} has no [portfolio] section", config.display());
+                    std::process::exit(1);
+                }
+            };
+            let report = match cfg.validate().and_then(|_| PortfolioReport::new(&cfg, portfolio)) {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("Error calculating portfolio: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report).expect("reports serialize"));
+            } else {
+                print_portfolio(&report);
+            }
+        }
+
+        Commands::ExportFix {
+            orders: args,
+            sender_comp_id,
+            target_comp_id,
+            account,
+            seq_num,
+            readable,
+            output,
+            force,
+        } => {
+            check_output(output.as_deref(), force);
+            let (orders, options) = build_orders(args, cli.config_format);
+            let session = FixSession {
+                account,
+                first_seq_num: seq_num,
+                ..FixSession::new(sender_comp_id, target_comp_id)
+            };
+            let messages = match fix::new_order_singles(&orders, &options, &session, chrono::Utc::now()) {
+                Ok(m) => m,
+                Err(e) => {
+                    eprintln!("Error exporting FIX messages: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let mut text = String::new();
+            for message in messages {
+                if readable {
+                    text.push_str(&message.replace(fix::SOH, "|"));
+                } else {
+                    text.push_str(&message);
+                }
+                text.push('\n');
+            }
+            write_output(output.as_deref(), &text, "FIX messages");
+        }
+
+        Commands::Start { config, strategy, state, force } => {
+            if state.exists() && !force {
+                eprintln!("Error: {} already exists (use --force to overwrite)", state.display());
+                std::process::exit(1);
+            }
+
+            let cfg = load_config(&config, cli.config_format, &Override::from_env());
+            let tracked = tracked_config(&cfg, strategy.as_deref());
+            let grid_state = match tracked.and_then(|c| GridState::new(c, strategy)) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Error starting grid: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            save_state(&grid_state, &state);
+            println!("Tracking started in {}", state.display());
+            print_state(&grid_state);
+        }
+
+        Commands::Fill { level, state } => {
+            let mut grid_state = load_state(&state);
+            let result = match grid_state.fill(level) {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            save_state(&grid_state, &state);
+            println!("Filled level {}: {:.2} at {:.2}", level, result.position_size, result.grid_price);
+            print_state(&grid_state);
+        }
+
+        Commands::Record { price, quantity, fee, time, level, state } => {
+            let mut grid_state = load_state(&state);
+            let execution = Execution {
+                price,
+                quantity,
+                fee,
+                timestamp: time.unwrap_or_else(chrono::Utc::now),
+            };
+            let level = match grid_state.record(level, execution) {
+                Ok(l) => l,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            save_state(&grid_state, &state);
+            println!("Recorded {:.2} at {:.2} for level {}", quantity, price, level);
+            print_reconciliation(&grid_state);
+        }
+
+        Commands::ImportTrades { file, layout, mapping, symbol, state } => {
+            let mapping = match (layout, mapping) {
+                (Some(layout), _) => layout.mapping(),
+                (None, Some(path)) => match ColumnMapping::from_file(&path) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        eprintln!("Error loading column mapping: {}", e);
+                        std::process::exit(1);
+                    }
+                },
+                (None, None) => unreachable!("clap requires --layout or --mapping"),
+            };
+            let mut grid_state = load_state(&state);
+            let imported = match trades::read_file(&file, &mapping)
+                .and_then(|t| trades::import(&mut grid_state, t, symbol.as_deref()))
+            {
+                Ok(i) => i,
+                Err(e) => {
+                    eprintln!("Error importing trades: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            save_state(&grid_state, &state);
+            println!("Imported {} buy(s) from {}", imported.levels.len(), file.display());
+            if imported.sells > 0 {
+                println!("Skipped {} sell(s)", imported.sells);
+            }
+            if imported.other_symbols > 0 {
+                println!("Skipped {} trade(s) for other symbols", imported.other_symbols);
+            }
+            print_reconciliation(&grid_state);
+        }
+
+        Commands::Reconcile { state } => {
+            print_reconciliation(&load_state(&state));
+        }
+
+        Commands::Status { state } => {
+            print_state(&load_state(&state));
+        }
+
+        Commands::Serve { bind, config } => {
+            let cfg = config.map(|path| load_config(&path, cli.config_format, &Override::from_env()));
+            let server = match Server::bind(&bind, cfg) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Error starting server: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            match server.local_addr() {
+                Some(address) => println!("Listening on http://{}", address),
+                None => println!("Listening on {}", bind),
+            }
+            server.run();
+        }
+
+        #[cfg(feature = "grpc")]
+        Commands::ServeGrpc { bind } => {
+            let runtime = match tokio::runtime::Runtime::new() {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("Error starting server: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            runtime.block_on(async {
+                let (listener, address) = match grpc::bind(&bind).await {
+                    Ok(l) => l,
+                    Err(e) => {
+                        eprintln!("Error starting server: {}", e);
+                        std::process::exit(1);
+                    }
+                };
+                println!("Listening for gRPC on {}", address);
+                if let Err(e) = grpc::serve_on(listener).await {
+                    eprintln!("Error running server: {}", e);
+                    std::process::exit(1);
+                }
+            });
+        }
+
+        Commands::Tui { config } => {
+            // The editor saves back to a single file, so includes stay unmerged
+            let format = cli.config_format.unwrap_or_else(|| Format::detect(&config));
+            let cfg = match Config::from_file_as(&config, format) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("Error loading config file: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            check_lints(&cfg, Some(&config));
+
+            if let Err(e) = tui::run(cfg, config) {
+                eprintln!("Error running editor: {}", e);
+                std::process::exit(1);
+            }
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,338 @@
+use crate::error::{Error, Result};
+use crate::import::Side;
+use crate::state::LadderLevel;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Longest client order ID prefix; IDs stay within the 32 characters every
+/// supported exchange accepts
+pub const MAX_PREFIX_LEN: usize = 28;
+
+/// Layout of an exported order file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OrderFormat {
+    /// One order per row
+    Csv,
+    /// Array of order objects
+    Json,
+    /// Binance `batchOrders` parameter
+    Binance,
+    /// OKX batch order request body
+    Okx,
+}
+
+impl OrderFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderFormat::Csv => "csv",
+            OrderFormat::Json => "json",
+            OrderFormat::Binance => "binance",
+            OrderFormat::Okx => "okx",
+        }
+    }
+}
+
+/// How ladder levels become orders
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderOptions {
+    /// Symbol as the exchange names it, e.g. `BTCUSDT` or `BTC-USDT`
+    pub symbol: String,
+    /// Price increment; prices are rounded down to it
+    pub tick_size: Option<f64>,
+    /// Quantity increment; quantities are rounded down to it
+    pub lot_size: Option<f64>,
+    /// Client order IDs are the prefix followed by the level number
+    pub id_prefix: String,
+}
+
+impl OrderOptions {
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            tick_size: None,
+            lot_size: None,
+            id_prefix: "grid".to_string(),
+        }
+    }
+
+    /// `price` with as many decimals as the tick size
+    pub fn format_price(&self, price: f64) -> String {
+        format_step(self.tick_size, price)
+    }
+
+    /// `quantity` with as many decimals as the lot size
+    pub fn format_quantity(&self, quantity: f64) -> String {
+        format_step(self.lot_size, quantity)
+    }
+}
+
+/// A limit order for one ladder level
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Order {
+    /// 1-based position in `grid.levels`
+    pub level: usize,
+    pub client_order_id: String,
+    pub symbol: String,
+    pub side: Side,
+    pub price: f64,
+    pub quantity: f64,
+}
+
+/// An increment prices or quantities are rounded to
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    size: f64,
+    decimals: usize,
+}
+
+impl Step {
+    fn new(size: f64, name: &str) -> Result<Self> {
+        if size <= 0.0 || !size.is_finite() {
+            return Err(Error::InvalidOrder {
+                message: format!("{} must be positive, got {}", name, size),
+            });
+        }
+        let text = size.to_string();
+        let decimals = text
+            .split_once('.')
+            .map_or(0, |(_, fraction)| fraction.len());
+        Ok(Self { size, decimals })
+    }
+
+    /// The largest multiple of the step not above `value`. A value within
+    /// floating point error of a multiple counts as that multiple.
+    fn round_down(&self, value: f64) -> f64 {
+        let units = (value / self.size + 1e-9).floor();
+        format!("{:.*}", self.decimals, units * self.size)
+            .parse()
+            .expect("formatted number parses")
+    }
+}
+
+/// `value` with the precision of `step`, or as short as possible without one
+fn format_step(step: Option<f64>, value: f64) -> String {
+    match step.and_then(|s| Step::new(s, "Step").ok()) {
+        Some(step) => format!("{:.*}", step.decimals, value),
+        None => value.to_string(),
+    }
+}
+
+/// Turn ladder levels into buy limit orders, rounding prices and
+/// quantities down to the tick and lot sizes
+pub fn build_orders(levels: &[LadderLevel], options: &OrderOptions) -> Result<Vec<Order>> {
+    check_prefix(&options.id_prefix)?;
+    let tick = options
+        .tick_size
+        .map(|s| Step::new(s, "Tick size"))
+        .transpose()?;
+    let lot = options
+        .lot_size
+        .map(|s| Step::new(s, "Lot size"))
+        .transpose()?;
+
+    levels
+        .iter()
+        .map(|l| {
+            let price = tick.map_or(l.result.grid_price, |t| t.round_down(l.result.grid_price));
+            let quantity = lot.map_or(l.result.position_size, |s| {
+                s.round_down(l.result.position_size)
+            });
+            if price <= 0.0 {
+                return Err(Error::InvalidOrder {
+                    message: format!("Level {} has no positive price ({})", l.level, price),
+                });
+            }
+            if quantity <= 0.0 {
+                return Err(Error::InvalidOrder {
+                    message: format!(
+                        "Level {} quantity {} rounds to zero with lot size {}",
+                        l.level,
+                        l.result.position_size,
+                        options.lot_size.unwrap_or_default()
+                    ),
+                });
+            }
+            Ok(Order {
+                level: l.level,
+                client_order_id: format!("{}{}", options.id_prefix, l.level),
+                symbol: options.symbol.clone(),
+                side: Side::Buy,
+                price,
+                quantity,
+            })
+        })
+        .collect()
+}
+
+/// Render orders in `format`. Exchange templates carry prices and
+/// quantities as strings with the tick and lot precision.
+pub fn render(orders: &[Order], format: OrderFormat, options: &OrderOptions) -> Result<String> {
+    let price = |o: &Order| options.format_price(o.price);
+    let quantity = |o: &Order| options.format_quantity(o.quantity);
+
+    let document: Value = match format {
+        OrderFormat::Csv => return to_csv(orders),
+        OrderFormat::Json => serde_json::to_value(orders).map_err(|e| Error::Serialize {
+            message: e.to_string(),
+        })?,
+        OrderFormat::Binance => orders
+            .iter()
+            .map(|o| {
+                json!({
+                    "symbol": o.symbol,
+                    "side": o.side.as_str().to_uppercase(),
+                    "type": "LIMIT",
+                    "timeInForce": "GTC",
+                    "quantity": quantity(o),
+                    "price": price(o),
+                    "newClientOrderId": o.client_order_id,
+                })
+            })
+            .collect(),
+        OrderFormat::Okx => orders
+            .iter()
+            .map(|o| {
+                json!({
+                    "instId": o.symbol,
+                    "tdMode": "cash",
+                    "clOrdId": o.client_order_id,
+                    "side": o.side.as_str(),
+                    "ordType": "limit",
+                    "px": price(o),
+                    "sz": quantity(o),
+                })
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&document)
+        .map(|s| s + "\n")
+        .map_err(|e| Error::Serialize {
+            message: e.to_string(),
+        })
+}
+
+fn to_csv(orders: &[Order]) -> Result<String> {
+    let serialize_error = |e: &dyn std::fmt::Display| Error::Serialize {
+        message: e.to_string(),
+    };
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for order in orders {
+        writer.serialize(order).map_err(|e| serialize_error(&e))?;
+    }
+    let bytes = writer.into_inner().map_err(|e| serialize_error(&e))?;
+    String::from_utf8(bytes).map_err(|e| serialize_error(&e))
+}
+
+/// Client order IDs must be accepted by every format, so the prefix is
+/// limited to ASCII letters and digits
+fn check_prefix(prefix: &str) -> Result<()> {
+    if prefix.is_empty()
+        || prefix.len() > MAX_PREFIX_LEN
+        || !prefix.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(Error::InvalidOrder {
+            message: format!(
+                "Client order ID prefix must be 1 to {} ASCII letters or digits, got '{}'",
+                MAX_PREFIX_LEN, prefix
+            ),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::state::GridState;
+    use crate::PositionMode;
+
+    fn ladder() -> Vec<LadderLevel> {
+        let mut config = Config::test(
+            &[1.0, 2.5],
+            PositionMode::IncrementMultiple,
+            0.0153,
+            Some(1.5),
+        );
+        config.base.initial_price = 101.37;
+        GridState::new(config, None).unwrap().remaining()
+    }
+
+    fn options() -> OrderOptions {
+        OrderOptions {
+            tick_size: Some(0.01),
+            lot_size: Some(0.001),
+            ..OrderOptions::new("BTCUSDT")
+        }
+    }
+
+    #[test]
+    fn test_rounding_and_ids() {
+        let orders = build_orders(&ladder(), &options()).unwrap();
+        // 101.37 * 0.99 = 100.3563 and 101.37 * 0.975 = 98.83575
+        assert_eq!(orders[0].price, 100.35);
+        assert_eq!(orders[1].price, 98.83);
+        // 0.0153 and 0.02295
+        assert_eq!(orders[0].quantity, 0.015);
+        assert_eq!(orders[1].quantity, 0.022);
+        assert_eq!(orders[1].client_order_id, "grid2");
+        assert_eq!(orders[0].side, Side::Buy);
+
+        // Values already on the grid are kept despite floating point error
+        let step = Step::new(0.1, "Tick size").unwrap();
+        assert_eq!(step.round_down(0.3), 0.3);
+        assert_eq!(step.round_down(0.1 + 0.2), 0.3);
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let coarse = OrderOptions {
+            lot_size: Some(1.0),
+            ..options()
+        };
+        let err = build_orders(&ladder(), &coarse).unwrap_err();
+        assert!(err.to_string().contains("rounds to zero"), "{}", err);
+
+        let prefix = OrderOptions {
+            id_prefix: "my-grid".to_string(),
+            ..options()
+        };
+        assert!(build_orders(&ladder(), &prefix).is_err());
+
+        let tick = OrderOptions {
+            tick_size: Some(0.0),
+            ..options()
+        };
+        assert!(build_orders(&ladder(), &tick).is_err());
+    }
+
+    #[test]
+    fn test_formats() {
+        let options = options();
+        let orders = build_orders(&ladder(), &options).unwrap();
+
+        let csv = render(&orders, OrderFormat::Csv, &options).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "level,client_order_id,symbol,side,price,quantity");
+        assert_eq!(lines[1], "1,grid1,BTCUSDT,buy,100.35,0.015");
+
+        let json: Value =
+            serde_json::from_str(&render(&orders, OrderFormat::Json, &options).unwrap()).unwrap();
+        assert_eq!(json[1]["price"], 98.83);
+
+        let binance: Value =
+            serde_json::from_str(&render(&orders, OrderFormat::Binance, &options).unwrap())
+                .unwrap();
+        assert_eq!(binance[0]["side"], "BUY");
+        assert_eq!(binance[0]["price"], "100.35");
+        assert_eq!(binance[1]["quantity"], "0.022");
+        assert_eq!(binance[1]["newClientOrderId"], "grid2");
+
+        let okx: Value =
+            serde_json::from_str(&render(&orders, OrderFormat::Okx, &options).unwrap()).unwrap();
+        assert_eq!(okx[0]["px"], "100.35");
+        assert_eq!(okx[0]["sz"], "0.015");
+        assert_eq!(okx[0]["clOrdId"], "grid1");
+    }
+}
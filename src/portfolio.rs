@@ -0,0 +1,291 @@
+use crate::config::resolve::MAIN_CONFIG;
+use crate::config::Config;
+use crate::error::{Error, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The `[portfolio]` section: grids on several symbols drawing on one pool
+/// of capital
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PortfolioConfig {
+    /// Capital shared by every grid, in the quote currency
+    pub capital: f64,
+    pub assets: Vec<Asset>,
+}
+
+/// A symbol traded with one of the config's strategies
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Asset {
+    pub symbol: String,
+    /// Strategy the grid follows, or `main` for the main config
+    #[serde(default = "main_strategy")]
+    pub strategy: String,
+    /// Price the grid starts from, overriding the strategy's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_price: Option<f64>,
+}
+
+fn main_strategy() -> String {
+    MAIN_CONFIG.to_string()
+}
+
+impl PortfolioConfig {
+    /// Every invalid value in the section. Strategies are looked up in
+    /// `config`.
+    pub fn errors(&self, config: &Config) -> Vec<Error> {
+        let mut errors = Vec::new();
+        if self.capital <= 0.0 || !self.capital.is_finite() {
+            errors.push(Error::invalid_field(
+                "portfolio.capital",
+                format!("Capital must be positive, got {}", self.capital),
+            ));
+        }
+        if self.assets.is_empty() {
+            errors.push(Error::invalid_field(
+                "portfolio.assets",
+                "Portfolio needs at least one asset",
+            ));
+        }
+
+        for (i, asset) in self.assets.iter().enumerate() {
+            let field = |name: &str| format!("portfolio.assets[{}].{}", i, name);
+            if asset.symbol.trim().is_empty() {
+                errors.push(Error::invalid_field(field("symbol"), "Symbol is empty"));
+            } else if self.assets[..i].iter().any(|a| a.symbol == asset.symbol) {
+                errors.push(Error::invalid_field(
+                    field("symbol"),
+                    format!("Duplicate portfolio symbol '{}'", asset.symbol),
+                ));
+            }
+            if asset.strategy != MAIN_CONFIG && config.strategy(&asset.strategy).is_err() {
+                errors.push(Error::invalid_field(
+                    field("strategy"),
+                    format!("Unknown strategy '{}' in portfolio", asset.strategy),
+                ));
+            }
+            if let Some(price) = asset.initial_price.filter(|p| *p <= 0.0 || !p.is_finite()) {
+                errors.push(Error::invalid_field(
+                    field("initial_price"),
+                    format!("Initial price must be positive, got {}", price),
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// One asset's grid with every level filled
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AssetReport {
+    pub symbol: String,
+    pub strategy: String,
+    pub initial_price: f64,
+    pub levels: usize,
+    /// Position once every level has filled
+    pub position: f64,
+    pub average_price: f64,
+    /// Price of the lowest level
+    pub lowest_price: f64,
+    /// Capital the filled ladder takes
+    pub required: f64,
+    /// Share of the portfolio's capital the filled ladder takes, in percent
+    pub capital_percent: f64,
+    /// Loss of the filled position marked at the lowest level's price
+    pub worst_case_loss: f64,
+}
+
+/// Every ladder filling at once
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PortfolioReport {
+    pub capital: f64,
+    pub assets: Vec<AssetReport>,
+    /// Capital all ladders take together
+    pub required: f64,
+    /// Capital left over, negative when the ladders need more than there is
+    pub free: f64,
+    pub worst_case_loss: f64,
+    /// Worst-case loss as a share of capital, in percent
+    pub worst_case_loss_percent: f64,
+    /// Factor every position size would need to be scaled by for the
+    /// ladders to fit the capital; 1 when they already fit
+    pub scale: f64,
+}
+
+impl PortfolioReport {
+    /// Fill every asset's ladder and total the capital it takes
+    pub fn new(config: &Config, portfolio: &PortfolioConfig) -> Result<Self> {
+        let assets = portfolio
+            .assets
+            .iter()
+            .map(|asset| asset_report(config, asset, portfolio.capital))
+            .collect::<Result<Vec<_>>>()?;
+
+        let required: f64 = assets.iter().map(|a| a.required).sum();
+        let worst_case_loss: f64 = assets.iter().map(|a| a.worst_case_loss).sum();
+        Ok(Self {
+            capital: portfolio.capital,
+            required,
+            free: portfolio.capital - required,
+            worst_case_loss,
+            worst_case_loss_percent: worst_case_loss / portfolio.capital * 100.0,
+            scale: if required > portfolio.capital {
+                portfolio.capital / required
+            } else {
+                1.0
+            },
+            assets,
+        })
+    }
+}
+
+fn asset_report(config: &Config, asset: &Asset, capital: f64) -> Result<AssetReport> {
+    let mut grid = if asset.strategy == MAIN_CONFIG {
+        Config {
+            portfolio: None,
+            ..config.clone()
+        }
+    } else {
+        config.strategy(&asset.strategy)?.to_config(config)?
+    };
+    if let Some(price) = asset.initial_price {
+        grid.base.initial_price = price;
+    }
+    let strategy = &asset.strategy;
+    grid.validate().map_err(|e| e.in_strategy(strategy))?;
+
+    let mut calculator = grid.calculator();
+    // Levels need not be sorted, so the last one is not always the lowest
+    let lowest_price = grid
+        .grid
+        .levels
+        .iter()
+        .map(|&percent| calculator.calculate_grid(percent).grid_price)
+        .fold(f64::INFINITY, f64::min);
+    let position = calculator.current_position();
+    let required = calculator.total_cost();
+    Ok(AssetReport {
+        symbol: asset.symbol.clone(),
+        strategy: asset.strategy.clone(),
+        initial_price: grid.base.initial_price,
+        levels: grid.grid.levels.len(),
+        position,
+        average_price: calculator.average_price(),
+        lowest_price,
+        required,
+        capital_percent: required / capital * 100.0,
+        worst_case_loss: required - position * lowest_price,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GridConfig;
+
+    const CONFIG: &str = r#"
+[base]
+initial_price = 100.0
+grid_type = "fixed"
+
+[grid]
+levels = [10.0, 20.0]
+
+[position]
+mode = "fixed"
+base_size = 10.0
+
+[[strategies]]
+name = "eth"
+extends = "main"
+base = { initial_price = 2000.0 }
+position = { base_size = 1.0 }
+
+[portfolio]
+capital = 5000.0
+
+[[portfolio.assets]]
+symbol = "SOLUSDT"
+
+[[portfolio.assets]]
+symbol = "ETHUSDT"
+strategy = "eth"
+
+[[portfolio.assets]]
+symbol = "AVAXUSDT"
+initial_price = 50.0
+"#;
+
+    fn config() -> Config {
+        toml::from_str(CONFIG).unwrap()
+    }
+
+    #[test]
+    fn test_all_ladders_fill() {
+        let config = config();
+        assert!(config.validate().is_ok(), "{:?}", config.errors());
+        let report = PortfolioReport::new(&config, config.portfolio.as_ref().unwrap()).unwrap();
+
+        // 10 at 90 and 10 at 80
+        let sol = &report.assets[0];
+        assert_eq!(
+            (sol.strategy.as_str(), sol.position, sol.required),
+            ("main", 20.0, 1700.0)
+        );
+        assert_eq!(sol.lowest_price, 80.0);
+        assert_eq!(sol.worst_case_loss, 100.0);
+        assert_eq!(sol.capital_percent, 34.0);
+
+        // 1 at 1800 and 1 at 1600
+        let eth = &report.assets[1];
+        assert_eq!(
+            (eth.initial_price, eth.required, eth.worst_case_loss),
+            (2000.0, 3400.0, 200.0)
+        );
+
+        // 10 at 45 and 10 at 40
+        assert_eq!(report.assets[2].required, 850.0);
+
+        assert_eq!(report.required, 5950.0);
+        assert_eq!(report.free, -950.0);
+        assert_eq!(report.worst_case_loss, 350.0);
+        assert!((report.worst_case_loss_percent - 7.0).abs() < 1e-9);
+        assert!((report.scale - 5000.0 / 5950.0).abs() < 1e-12);
+
+        // Unsorted levels are still marked at the lowest one
+        let unsorted = Config {
+            grid: GridConfig {
+                levels: vec![20.0, 10.0],
+            },
+            ..config.clone()
+        };
+        let report = PortfolioReport::new(&unsorted, unsorted.portfolio.as_ref().unwrap()).unwrap();
+        let sol = &report.assets[0];
+        assert_eq!((sol.lowest_price, sol.worst_case_loss), (80.0, 100.0));
+    }
+
+    #[test]
+    fn test_invalid_section() {
+        let config: Config = toml::from_str(
+            &CONFIG
+                .replace("capital = 5000.0", "capital = 0.0")
+                .replace("strategy = \"eth\"", "strategy = \"btc\"")
+                .replace("AVAXUSDT", "SOLUSDT"),
+        )
+        .unwrap();
+        let fields: Vec<String> = config
+            .errors()
+            .iter()
+            .filter_map(|e| e.field().map(String::from))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "portfolio.capital",
+                "portfolio.assets[1].strategy",
+                "portfolio.assets[2].symbol"
+            ]
+        );
+    }
+}
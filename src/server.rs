@@ -0,0 +1,368 @@
+use crate::config::format::Format;
+use crate::config::{lint, migrate, Config};
+use crate::diagnostics::{self, Diagnostic};
+use crate::error::{Error, Result};
+use crate::state::GridState;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::net::SocketAddr;
+
+/// Largest request body the server reads
+pub const MAX_BODY: u64 = 1 << 20;
+
+/// An HTTP API for the calculator. Request and response bodies are JSON;
+/// configs may also be sent as TOML or YAML with a matching Content-Type.
+pub struct Server {
+    http: tiny_http::Server,
+    /// Config served by `GET /strategies` and used by `POST /calculate`
+    /// requests without a body
+    config: Option<Config>,
+}
+
+/// A response before it is written: status code and JSON body
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Reply {
+    fn ok(body: Value) -> Self {
+        Reply { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Reply {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+impl Server {
+    /// Listen on `address`, e.g. `127.0.0.1:8080`. Port 0 picks a free port.
+    pub fn bind(address: &str, config: Option<Config>) -> Result<Self> {
+        let http = tiny_http::Server::http(address).map_err(|e| Error::Bind {
+            address: address.to_string(),
+            message: e.to_string(),
+        })?;
+        Ok(Server { http, config })
+    }
+
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    /// Answer requests until the process exits
+    pub fn run(&self) {
+        for request in self.http.incoming_requests() {
+            self.respond(request);
+        }
+    }
+
+    fn respond(&self, mut request: tiny_http::Request) {
+        let format = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Content-Type"))
+            .map_or(Format::Json, |h| content_format(h.value.as_str()));
+
+        let mut body = String::new();
+        let read = request
+            .as_reader()
+            .take(MAX_BODY + 1)
+            .read_to_string(&mut body);
+        let reply = match read {
+            Err(e) => Reply::error(400, format!("Cannot read request body: {}", e)),
+            Ok(_) if body.len() as u64 > MAX_BODY => Reply::error(
+                413,
+                format!("Request body is larger than {} bytes", MAX_BODY),
+            ),
+            Ok(_) => self.route(request.method().as_str(), request.url(), &body, format),
+        };
+
+        let header = tiny_http::Header::from_bytes("Content-Type", "application/json")
+            .expect("static header is valid");
+        let text = serde_json::to_string(&reply.body).expect("JSON values serialize") + "\n";
+        let response = tiny_http::Response::from_string(text)
+            .with_status_code(reply.status)
+            .with_header(header);
+        // The client may already have gone away; nothing to do about it
+        let _ = request.respond(response);
+    }
+
+    /// Dispatch a request to its endpoint
+    pub fn route(&self, method: &str, url: &str, body: &str, format: Format) -> Reply {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let strategy = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("strategy="))
+            .map(decode);
+
+        match (method, path) {
+            ("POST", "/calculate") => self.calculate(body, format, strategy.as_deref()),
+            ("POST", "/validate") => validate(body, format),
+            ("GET", "/strategies") => self.strategies(),
+            (_, "/calculate" | "/validate" | "/strategies") => {
+                Reply::error(405, format!("Method {} not allowed for {}", method, path))
+            }
+            _ => Reply::error(404, format!("No endpoint at {}", path)),
+        }
+    }
+
+    /// `POST /calculate`: the ladder of the config in the body, or of the
+    /// server's config if the body is empty. `?strategy=<name>` calculates
+    /// a strategy of that config instead.
+    fn calculate(&self, body: &str, format: Format, strategy: Option<&str>) -> Reply {
+        let config = if body.trim().is_empty() {
+            match &self.config {
+                Some(config) => config.clone(),
+                None => return Reply::error(400, "Request body must contain a config"),
+            }
+        } else {
+            match migrate::parse::<Config>(body, format) {
+                Ok(config) => config,
+                Err(e) => return Reply::error(400, format!("Invalid config: {}", e)),
+            }
+        };
+
+        let denied: Vec<Diagnostic> = lint::lint(&config)
+            .into_iter()
+            .filter(|d| d.severity == diagnostics::Severity::Error)
+            .collect();
+        if !denied.is_empty() {
+            return Reply {
+                status: 422,
+                body: json!({ "error": "Config violates denied lints", "diagnostics": denied }),
+            };
+        }
+
+        let resolved = match strategy {
+            Some(name) => config.strategy(name).and_then(|s| s.to_config(&config)),
+            None => Ok(config),
+        };
+        match resolved.and_then(|c| GridState::new(c, None)) {
+            Ok(state) => Reply::ok(json!({ "levels": state.remaining() })),
+            Err(Error::StrategyNotFound { name }) => {
+                Reply::error(404, format!("Strategy '{}' not found in config", name))
+            }
+            Err(e) => Reply::error(422, e.to_string()),
+        }
+    }
+
+    /// `GET /strategies`: every strategy of the server's config, resolved
+    fn strategies(&self) -> Reply {
+        let Some(config) = &self.config else {
+            return Reply::error(404, "No config loaded; start the server with --config");
+        };
+        let strategies: Vec<Value> = config
+            .strategies
+            .iter()
+            .flatten()
+            .map(|strategy| match strategy.to_config(config) {
+                Ok(resolved) => json!({
+                    "name": strategy.name,
+                    "extends": strategy.extends,
+                    "config": resolved,
+                }),
+                Err(e) => json!({
+                    "name": strategy.name,
+                    "extends": strategy.extends,
+                    "error": e.to_string(),
+                }),
+            })
+            .collect();
+        Reply::ok(json!({ "strategies": strategies }))
+    }
+}
+
+/// `POST /validate`: every problem with the config in the body. A body
+/// that does not parse is reported as an invalid config, not a bad request.
+fn validate(body: &str, format: Format) -> Reply {
+    let diagnostics = match migrate::parse::<Config>(body, format) {
+        Ok(config) => config.diagnostics(),
+        Err(e) => vec![Diagnostic::error(None, e.to_string())],
+    };
+    Reply::ok(json!({
+        "valid": !diagnostics::has_errors(&diagnostics),
+        "diagnostics": diagnostics,
+    }))
+}
+
+fn content_format(content_type: &str) -> Format {
+    let media = content_type.split(';').next().unwrap_or_default().trim();
+    match media {
+        "application/toml" | "text/toml" => Format::Toml,
+        "application/yaml" | "application/x-yaml" | "text/yaml" => Format::Yaml,
+        _ => Format::Json,
+    }
+}
+
+/// Decode a percent-encoded query value
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::sync::Arc;
+    use std::thread;
+
+    const CONFIG: &str = r#"{
+        "base": { "initial_price": 100.0, "grid_type": "fixed" },
+        "grid": { "levels": [1.0, 2.0] },
+        "position": { "mode": "fixed", "base_size": 10.0 },
+        "strategies": [
+            { "name": "wide", "extends": "main", "grid": { "levels": [5.0, 10.0] } },
+            { "name": "broken" }
+        ]
+    }"#;
+
+    /// Start a server on a free localhost port, answering in the background
+    fn start(config: Option<Config>) -> SocketAddr {
+        let server = Arc::new(Server::bind("127.0.0.1:0", config).unwrap());
+        let address = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+        address
+    }
+
+    fn request(
+        address: SocketAddr,
+        method: &str,
+        url: &str,
+        headers: &str,
+        body: &str,
+    ) -> (u16, Value) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{}Content-Length: {}\r\n\r\n{}",
+            method,
+            url,
+            headers,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn test_calculate() {
+        let address = start(None);
+        let (status, body) = request(address, "POST", "/calculate", "", CONFIG);
+        assert_eq!(status, 200);
+        assert_eq!(body["levels"][1]["level"], 2);
+        assert_eq!(body["levels"][1]["grid_price"], 98.0);
+        assert_eq!(body["levels"][1]["total_position"], 20.0);
+
+        let (status, body) = request(address, "POST", "/calculate?strategy=wide", "", CONFIG);
+        assert_eq!(status, 200);
+        assert_eq!(body["levels"][0]["grid_price"], 95.0);
+
+        let (status, _) = request(address, "POST", "/calculate?strategy=none", "", CONFIG);
+        assert_eq!(status, 404);
+
+        let toml = "[base]\ninitial_price = 50.0\ngrid_type = \"fixed\"\n[grid]\nlevels = [10.0]\n[position]\nmode = \"fixed\"\nbase_size = 1.0\n";
+        let (status, body) = request(
+            address,
+            "POST",
+            "/calculate",
+            "Content-Type: application/toml\r\n",
+            toml,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["levels"][0]["grid_price"], 45.0);
+
+        let invalid = CONFIG.replace("[1.0, 2.0]", "[150.0]");
+        let (status, body) = request(address, "POST", "/calculate", "", &invalid);
+        assert_eq!(status, 422);
+        assert!(
+            body["error"].as_str().unwrap().contains("grid.levels"),
+            "{}",
+            body
+        );
+
+        let (status, _) = request(address, "POST", "/calculate", "", "{ not json");
+        assert_eq!(status, 400);
+    }
+
+    #[test]
+    fn test_validate() {
+        let address = start(None);
+        let (status, body) = request(address, "POST", "/validate", "", CONFIG);
+        assert_eq!(status, 200);
+        assert_eq!(body["valid"], false);
+        assert_eq!(body["diagnostics"][0]["path"], "strategies[1].base");
+        assert_eq!(body["diagnostics"][0]["severity"], "error");
+
+        let valid = CONFIG.replace(",\n            { \"name\": \"broken\" }", "");
+        let (_, body) = request(address, "POST", "/validate", "", &valid);
+        assert_eq!(body["valid"], true, "{}", body);
+
+        let (status, body) = request(address, "POST", "/validate", "", "[]");
+        assert_eq!(status, 200);
+        assert_eq!(body["valid"], false);
+    }
+
+    #[test]
+    fn test_strategies_and_routing() {
+        let config: Config = serde_json::from_str(CONFIG).unwrap();
+        let address = start(Some(config));
+
+        let (status, body) = request(address, "GET", "/strategies", "", "");
+        assert_eq!(status, 200);
+        assert_eq!(body["strategies"][0]["name"], "wide");
+        assert_eq!(body["strategies"][0]["config"]["grid"]["levels"][1], 10.0);
+        assert!(body["strategies"][1]["error"].is_string());
+
+        // Without a body, /calculate uses the server's config
+        let (status, body) = request(address, "POST", "/calculate?strategy=wide", "", "");
+        assert_eq!(status, 200);
+        assert_eq!(body["levels"][1]["grid_price"], 90.0);
+
+        assert_eq!(request(address, "GET", "/calculate", "", "").0, 405);
+        assert_eq!(request(address, "GET", "/nothing", "", "").0, 404);
+
+        let address = start(None);
+        assert_eq!(request(address, "GET", "/strategies", "", "").0, 404);
+    }
+
+    #[test]
+    fn test_decode_query() {
+        assert_eq!(decode("%E4%BF%9D%E5%AE%88+a"), "保守 a");
+        assert_eq!(decode("50%"), "50%");
+    }
+}
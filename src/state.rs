@@ -0,0 +1,549 @@
+use crate::config::Config;
+use crate::error::{Error, Result};
+use crate::grid_calculator::{Execution, GridCalculator, GridResult, LevelFill};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Version of the state file layout this build reads and writes
+pub const STATE_VERSION: u32 = 1;
+
+/// A grid being tracked live: the config it was started from, the levels
+/// filled so far and the calculator state after those fills. Saved as JSON
+/// so tracking can resume across runs.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GridState {
+    version: u32,
+    /// Strategy the config was resolved from, if any
+    pub strategy: Option<String>,
+    pub config: Config,
+    /// Filled levels, 1-based, in the order they were filled
+    filled: Vec<usize>,
+    /// Actual executions in the order they were recorded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    executions: Vec<RecordedExecution>,
+    calculator: GridCalculator,
+}
+
+/// An execution and the 1-based level it was matched to
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RecordedExecution {
+    pub level: usize,
+    #[serde(flatten)]
+    pub execution: Execution,
+}
+
+/// How a level's executions compare with the plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelStatus {
+    /// Executed the planned size
+    Filled,
+    /// Executed less than the planned size
+    Partial,
+    /// Executed more than the planned size
+    OverFilled,
+    /// Not filled although the price traded through the level
+    Missing,
+    /// Not reached yet
+    Pending,
+}
+
+impl LevelStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LevelStatus::Filled => "filled",
+            LevelStatus::Partial => "partial",
+            LevelStatus::OverFilled => "over-filled",
+            LevelStatus::Missing => "missing",
+            LevelStatus::Pending => "pending",
+        }
+    }
+}
+
+/// One level of a reconciliation
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelReport {
+    /// 1-based position in `grid.levels`
+    pub level: usize,
+    pub percent: f64,
+    pub status: LevelStatus,
+    pub planned: GridResult,
+    /// What the level booked; `None` for levels not filled
+    pub fill: Option<LevelFill>,
+}
+
+/// Relative difference in size below which a level counts as filled as
+/// planned
+const TOLERANCE: f64 = 1e-9;
+
+/// Profit and loss of the position at a market price
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Pnl {
+    pub position: f64,
+    /// What the position cost, fees included
+    pub cost: f64,
+    pub fees: f64,
+    pub average_price: f64,
+    pub mark_price: f64,
+    pub market_value: f64,
+    /// Market value less cost. Grids only buy, so nothing is realized.
+    pub unrealized: f64,
+}
+
+/// A level of the ladder with its computed price and size
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LadderLevel {
+    /// 1-based position in `grid.levels`
+    pub level: usize,
+    pub percent: f64,
+    #[serde(flatten)]
+    pub result: GridResult,
+}
+
+impl GridState {
+    /// Start tracking `config` with nothing filled. The config must be
+    /// complete; strategies, includes and the portfolio are dropped.
+    pub fn new(config: Config, strategy: Option<String>) -> Result<Self> {
+        config.validate()?;
+        let config = Config {
+            include: None,
+            strategies: None,
+            portfolio: None,
+            ..config
+        };
+        Ok(Self {
+            version: STATE_VERSION,
+            strategy,
+            calculator: config.calculator(),
+            config,
+            filled: Vec::new(),
+            executions: Vec::new(),
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let parse_error = |message: String| Error::Parse {
+            path: Some(path.to_path_buf()),
+            message,
+        };
+        let contents = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let state: GridState =
+            serde_json::from_str(&contents).map_err(|e| parse_error(e.to_string()))?;
+        if state.version != STATE_VERSION {
+            return Err(parse_error(format!(
+                "Unsupported state version {} (expected {})",
+                state.version, STATE_VERSION
+            )));
+        }
+        Ok(state)
+    }
+
+    /// Save the state, replacing `path` only once the new file is written
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let contents = serde_json::to_string_pretty(self).map_err(|e| Error::Serialize {
+            message: e.to_string(),
+        })? + "\n";
+
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let io_error = |source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        };
+        fs::write(&temp, contents).map_err(io_error)?;
+        fs::rename(&temp, path).map_err(io_error)
+    }
+
+    /// Record that the 1-based `level` filled as planned, sizing it from
+    /// the position built so far
+    pub fn fill(&mut self, level: usize) -> Result<GridResult> {
+        self.check_level(level)?;
+        if self.filled.contains(&level) {
+            return Err(Error::InvalidFill {
+                message: format!("Level {} is already filled", level),
+            });
+        }
+
+        let result = self
+            .calculator
+            .calculate_grid(self.config.grid.levels[level - 1]);
+        self.filled.push(level);
+        Ok(result)
+    }
+
+    /// Record an actual execution against `level`, or against the level
+    /// whose planned price is closest if `None`. Executions for a level that
+    /// already filled add to it; a level filled as planned with `fill` keeps
+    /// that fill as an execution at the planned price and size. Returns the
+    /// level it was matched to.
+    pub fn record(&mut self, level: Option<usize>, execution: Execution) -> Result<usize> {
+        let invalid = |message: String| Err(Error::InvalidFill { message });
+        if execution.price <= 0.0 || !execution.price.is_finite() {
+            return invalid(format!(
+                "Execution price must be positive, got {}",
+                execution.price
+            ));
+        }
+        if execution.quantity <= 0.0 || !execution.quantity.is_finite() {
+            return invalid(format!(
+                "Execution quantity must be positive, got {}",
+                execution.quantity
+            ));
+        }
+        if execution.fee < 0.0 || !execution.fee.is_finite() {
+            return invalid(format!(
+                "Execution fee must not be negative, got {}",
+                execution.fee
+            ));
+        }
+
+        let level = match level {
+            Some(level) => {
+                self.check_level(level)?;
+                level
+            }
+            None => self.nearest_level(execution.price),
+        };
+        if self.filled.contains(&level) && !self.executions.iter().any(|e| e.level == level) {
+            let planned = self
+                .replay()
+                .1
+                .into_iter()
+                .find(|(l, _)| *l == level)
+                .map(|(_, fill)| fill.planned);
+            if let Some(planned) = planned {
+                self.executions.push(RecordedExecution {
+                    level,
+                    execution: Execution {
+                        price: planned.grid_price,
+                        quantity: planned.position_size,
+                        fee: 0.0,
+                        timestamp: execution.timestamp,
+                    },
+                });
+            }
+        }
+        self.executions.push(RecordedExecution { level, execution });
+        if !self.filled.contains(&level) {
+            self.filled.push(level);
+        }
+        self.calculator = self.replay().0;
+        Ok(level)
+    }
+
+    /// Every recorded execution, in the order they were recorded
+    pub fn executions(&self) -> &[RecordedExecution] {
+        &self.executions
+    }
+
+    /// Compare every level with the plan, in ladder order. Levels above the
+    /// lowest fill price that have not filled are reported as missing.
+    pub fn reconcile(&self) -> Vec<LevelReport> {
+        let fills = self.replay().1;
+        let lowest = fills
+            .iter()
+            .map(|(_, fill)| fill.executed.grid_price)
+            .fold(f64::INFINITY, f64::min);
+
+        let mut reports: Vec<LevelReport> = fills
+            .into_iter()
+            .map(|(level, fill)| {
+                let (planned, executed) = (fill.planned.position_size, fill.executed.position_size);
+                let status = if executed > planned * (1.0 + TOLERANCE) {
+                    LevelStatus::OverFilled
+                } else if executed < planned * (1.0 - TOLERANCE) {
+                    LevelStatus::Partial
+                } else {
+                    LevelStatus::Filled
+                };
+                LevelReport {
+                    level,
+                    percent: self.config.grid.levels[level - 1],
+                    status,
+                    planned: fill.planned.clone(),
+                    fill: Some(fill),
+                }
+            })
+            .collect();
+        reports.extend(self.remaining().into_iter().map(|l| LevelReport {
+            level: l.level,
+            percent: l.percent,
+            status: if l.result.grid_price >= lowest {
+                LevelStatus::Missing
+            } else {
+                LevelStatus::Pending
+            },
+            planned: l.result,
+            fill: None,
+        }));
+        reports.sort_by_key(|r| r.level);
+        reports
+    }
+
+    fn check_level(&self, level: usize) -> Result<()> {
+        let count = self.config.grid.levels.len();
+        if level == 0 || level > count {
+            return Err(Error::InvalidFill {
+                message: format!(
+                    "Level {} is outside the ladder (levels 1 to {})",
+                    level, count
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// The level whose planned price is closest to `price`
+    fn nearest_level(&self, price: f64) -> usize {
+        let filled = self
+            .replay()
+            .1
+            .into_iter()
+            .map(|(level, fill)| (level, fill.planned.grid_price));
+        let remaining = self
+            .remaining()
+            .into_iter()
+            .map(|l| (l.level, l.result.grid_price));
+        filled
+            .chain(remaining)
+            .min_by(|a, b| (a.1 - price).abs().total_cmp(&(b.1 - price).abs()))
+            .map_or(1, |(level, _)| level)
+    }
+
+    /// Rebuild the calculator from the config, filling levels in order
+    /// with their executions, or as planned if they have none
+    fn replay(&self) -> (GridCalculator, Vec<(usize, LevelFill)>) {
+        let mut calculator = self.config.calculator();
+        let mut fills = Vec::new();
+        for &level in &self.filled {
+            let percent = self.config.grid.levels[level - 1];
+            let executions: Vec<Execution> = self
+                .executions
+                .iter()
+                .filter(|e| e.level == level)
+                .map(|e| e.execution.clone())
+                .collect();
+            let fill = if executions.is_empty() {
+                let result = calculator.calculate_grid(percent);
+                LevelFill {
+                    planned: result.clone(),
+                    executed: result,
+                    fees: 0.0,
+                }
+            } else {
+                calculator.record_executions(percent, &executions)
+            };
+            fills.push((level, fill));
+        }
+        (calculator, fills)
+    }
+
+    pub fn calculator(&self) -> &GridCalculator {
+        &self.calculator
+    }
+
+    /// Filled levels with their results, in fill order
+    pub fn fills(&self) -> Vec<LadderLevel> {
+        self.filled
+            .iter()
+            .zip(self.calculator.get_history())
+            .map(|(&level, result)| LadderLevel {
+                level,
+                percent: self.config.grid.levels[level - 1],
+                result: result.clone(),
+            })
+            .collect()
+    }
+
+    /// Levels not filled yet, priced and sized as if they fill in ladder
+    /// order from the current state
+    pub fn remaining(&self) -> Vec<LadderLevel> {
+        let mut calculator = self.calculator.clone();
+        self.config
+            .grid
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(i, &percent)| (i + 1, percent))
+            .filter(|(level, _)| !self.filled.contains(level))
+            .map(|(level, percent)| LadderLevel {
+                level,
+                percent,
+                result: calculator.calculate_grid(percent),
+            })
+            .collect()
+    }
+
+    /// PnL of the filled position marked at `price`
+    pub fn pnl(&self, price: f64) -> Pnl {
+        let position = self.calculator.current_position();
+        let cost = self.calculator.total_cost();
+        let market_value = position * price;
+        Pnl {
+            position,
+            cost,
+            // Folded from 0.0 as an empty float `sum` is -0.0
+            fees: self
+                .executions
+                .iter()
+                .fold(0.0, |sum, e| sum + e.execution.fee),
+            average_price: self.calculator.average_price(),
+            mark_price: price,
+            market_value,
+            unrealized: market_value - cost,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PositionMode;
+
+    fn state() -> GridState {
+        let config = Config::test(
+            &[1.0, 2.0, 3.0],
+            PositionMode::CurrentMultiple,
+            100.0,
+            Some(2.0),
+        );
+        GridState::new(config, None).unwrap()
+    }
+
+    #[test]
+    fn test_fill_and_remaining() {
+        let mut state = state();
+        assert_eq!(state.remaining().len(), 3);
+
+        let first = state.fill(1).unwrap();
+        assert_eq!(first.grid_price, 99.0);
+        assert_eq!(first.position_size, 100.0);
+
+        let remaining = state.remaining();
+        assert_eq!(
+            remaining.iter().map(|l| l.level).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(remaining[0].result.position_size, 200.0);
+        assert_eq!(remaining[1].result.position_size, 600.0);
+        // Projecting the ladder leaves the state untouched
+        assert_eq!(state.calculator().current_position(), 100.0);
+
+        let third = state.fill(3).unwrap();
+        assert_eq!(third.grid_price, 97.0);
+        assert_eq!(third.position_size, 200.0);
+        assert_eq!(
+            state.fills().iter().map(|l| l.level).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+    }
+
+    #[test]
+    fn test_invalid_fills() {
+        let mut state = state();
+        state.fill(2).unwrap();
+        assert!(matches!(state.fill(2), Err(Error::InvalidFill { .. })));
+        let err = state.fill(4).unwrap_err();
+        assert!(err.to_string().contains("levels 1 to 3"), "{}", err);
+        assert!(state.fill(0).is_err());
+        assert_eq!(state.fills().len(), 1);
+    }
+
+    fn execution(price: f64, quantity: f64) -> Execution {
+        Execution {
+            price,
+            quantity,
+            fee: 0.5,
+            timestamp: "2024-05-01T12:00:00Z".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_reconcile() {
+        let mut state = state();
+        // Matched by price: 98.1 is closest to level 2's planned 98.0
+        assert_eq!(state.record(None, execution(98.1, 100.0)).unwrap(), 2);
+        assert_eq!(state.record(Some(2), execution(97.9, 50.0)).unwrap(), 2);
+        assert_eq!(state.calculator().current_position(), 150.0);
+
+        let reports = state.reconcile();
+        let statuses: Vec<_> = reports.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                LevelStatus::Missing,
+                LevelStatus::OverFilled,
+                LevelStatus::Pending
+            ]
+        );
+        let fill = reports[1].fill.as_ref().unwrap();
+        assert_eq!(fill.executed.position_size, 150.0);
+        assert_eq!(fill.fees, 1.0);
+        assert!((fill.executed.grid_price - (98.1 * 100.0 + 97.9 * 50.0) / 150.0).abs() < 1e-9);
+        // Unfilled levels are projected in ladder order from the executed
+        // position: level 1 would add 300, then level 3 twice 450
+        assert_eq!(reports[0].planned.position_size, 300.0);
+        assert_eq!(reports[2].planned.position_size, 900.0);
+
+        // Recording against level 1 later replays the ladder in fill order
+        state.record(Some(1), execution(99.0, 40.0)).unwrap();
+        let reports = state.reconcile();
+        assert_eq!(reports[0].status, LevelStatus::Partial);
+        assert_eq!(reports[0].planned.position_size, 300.0);
+        assert_eq!(state.executions().len(), 3);
+    }
+
+    #[test]
+    fn test_record_after_fill() {
+        let mut state = state();
+        state.fill(1).unwrap();
+        state.record(Some(1), execution(99.0, 20.0)).unwrap();
+
+        // The planned fill of 100 at 99 stays and the execution adds to it
+        assert_eq!(state.calculator().current_position(), 120.0);
+        assert!((state.calculator().total_cost() - 120.0 * 99.0 - 0.5).abs() < 1e-9);
+        assert_eq!(state.executions().len(), 2);
+        assert_eq!(state.reconcile()[0].status, LevelStatus::OverFilled);
+    }
+
+    #[test]
+    fn test_invalid_executions() {
+        let mut state = state();
+        assert!(state.record(None, execution(0.0, 10.0)).is_err());
+        assert!(state.record(None, execution(99.0, -1.0)).is_err());
+        assert!(state.record(Some(9), execution(99.0, 1.0)).is_err());
+        assert!(state.executions().is_empty());
+    }
+
+    #[test]
+    fn test_resume_from_file() {
+        let path = std::env::temp_dir().join(format!("grid-state-{}.json", std::process::id()));
+        let mut state = state();
+        state.fill(1).unwrap();
+        state.record(Some(2), execution(97.5, 200.0)).unwrap();
+        state.to_file(&path).unwrap();
+
+        let mut resumed = GridState::from_file(&path).unwrap();
+        assert_eq!(resumed.fills(), state.fills());
+        assert_eq!(resumed.executions(), state.executions());
+        assert_eq!(resumed.fill(3).unwrap(), state.fill(3).unwrap());
+
+        let newer = fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"version\": 1,", "\"version\": 99,");
+        fs::write(&path, newer).unwrap();
+        let err = GridState::from_file(&path).unwrap_err();
+        assert!(
+            err.to_string().contains("Unsupported state version"),
+            "{}",
+            err
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,36 @@
+use grid_calculator::GridResult;
+
+/// Grid levels as the comma-separated list the prompts and the editor read
+pub fn format_levels(levels: &[f64]) -> String {
+    levels
+        .iter()
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Rule above and below a ladder table
+pub fn rule() -> String {
+    format!("{:-<60}", "")
+}
+
+/// Column headings of a ladder table
+pub fn ladder_header() -> String {
+    format!(
+        "{:<5} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "Grid", "Level %", "Price", "Size", "Total", "Avg Price"
+    )
+}
+
+/// One level of a ladder table
+pub fn ladder_row(level: usize, percent: f64, result: &GridResult) -> String {
+    format!(
+        "{:<5} {:>10.2}% {:>10.2} {:>10.2} {:>10.2} {:>10.2}",
+        level,
+        percent,
+        result.grid_price,
+        result.position_size,
+        result.total_position,
+        result.average_price
+    )
+}
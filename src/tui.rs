@@ -0,0 +1,398 @@
+use clap::ValueEnum;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue, style};
+use grid_calculator::config::lint;
+use grid_calculator::{Config, Error, GridType, PositionMode};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::table;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    InitialPrice,
+    GridType,
+    Levels,
+    PositionMode,
+    BaseSize,
+    Multiplier,
+}
+
+impl Field {
+    const ALL: [Field; 6] = [
+        Field::InitialPrice,
+        Field::GridType,
+        Field::Levels,
+        Field::PositionMode,
+        Field::BaseSize,
+        Field::Multiplier,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Field::InitialPrice => "Initial Price",
+            Field::GridType => "Grid Type",
+            Field::Levels => "Levels",
+            Field::PositionMode => "Position Mode",
+            Field::BaseSize => "Base Size",
+            Field::Multiplier => "Multiplier",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    Continue,
+    Quit,
+}
+
+struct App {
+    config: Config,
+    path: PathBuf,
+    selected: usize,
+    input: Option<String>,
+    modified: bool,
+    status: String,
+}
+
+impl App {
+    fn new(config: Config, path: PathBuf) -> Self {
+        Self {
+            config,
+            path,
+            selected: 0,
+            input: None,
+            modified: false,
+            status: String::new(),
+        }
+    }
+
+    fn field(&self) -> Field {
+        Field::ALL[self.selected]
+    }
+
+    fn value(&self, field: Field) -> String {
+        match field {
+            Field::InitialPrice => format!("{:.2}", self.config.base.initial_price),
+            Field::GridType => self.config.base.grid_type.to_string(),
+            Field::Levels => table::format_levels(&self.config.grid.levels),
+            Field::PositionMode => self.config.position.mode.to_string(),
+            Field::BaseSize => format!("{:.2}", self.config.position.base_size),
+            Field::Multiplier => match self.config.position.multiplier {
+                Some(mult) => format!("{:.2}", mult),
+                None => "-".to_string(),
+            },
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Esc => {
+                    self.input = None;
+                    self.status = "Edit cancelled".to_string();
+                }
+                KeyCode::Enter => {
+                    let text = self.input.take().unwrap_or_default();
+                    match self.set_value(self.field(), &text) {
+                        Ok(()) => self.status.clear(),
+                        Err(e) => self.status = e,
+                    }
+                }
+                _ => {}
+            }
+            return Action::Continue;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = (self.selected + Field::ALL.len() - 1) % Field::ALL.len();
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1) % Field::ALL.len();
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('-') => self.adjust(-1.0),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('+') => self.adjust(1.0),
+            KeyCode::Enter => {
+                let current = match self.field() {
+                    Field::Multiplier if self.config.position.multiplier.is_none() => String::new(),
+                    field => self.value(field),
+                };
+                self.input = Some(current);
+            }
+            KeyCode::Char('s') => self.save(),
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    /// Nudge the selected field one step in `direction` (-1.0 or 1.0)
+    fn adjust(&mut self, direction: f64) {
+        match self.field() {
+            Field::InitialPrice => {
+                let price = &mut self.config.base.initial_price;
+                *price = round_cents(*price + direction * step_for(*price));
+            }
+            Field::GridType => {
+                self.config.base.grid_type = cycle(
+                    GridType::value_variants(),
+                    self.config.base.grid_type,
+                    direction,
+                );
+            }
+            Field::Levels => {
+                // Shift the whole ladder by a tenth of a percent
+                for level in &mut self.config.grid.levels {
+                    *level = round_cents(*level + direction * 0.1);
+                }
+            }
+            Field::PositionMode => {
+                self.config.position.mode = cycle(
+                    PositionMode::value_variants(),
+                    self.config.position.mode,
+                    direction,
+                );
+            }
+            Field::BaseSize => {
+                let size = &mut self.config.position.base_size;
+                *size = round_cents(*size + direction * step_for(*size));
+            }
+            Field::Multiplier => {
+                let mult = self.config.position.multiplier.unwrap_or(1.0);
+                self.config.position.multiplier = Some(round_cents(mult + direction * 0.1));
+            }
+        }
+        self.modified = true;
+        self.status.clear();
+    }
+
+    fn set_value(&mut self, field: Field, text: &str) -> Result<(), String> {
+        let text = text.trim();
+        let parse_number = |s: &str| {
+            s.parse::<f64>()
+                .map_err(|_| format!("Invalid number for {}: '{}'", field.label(), s))
+        };
+        match field {
+            Field::InitialPrice => self.config.base.initial_price = parse_number(text)?,
+            Field::GridType => {
+                self.config.base.grid_type = text.parse().map_err(|e: Error| e.to_string())?
+            }
+            Field::Levels => {
+                let levels = text
+                    .split(',')
+                    .filter(|s| !s.trim().is_empty())
+                    .map(|s| parse_number(s.trim()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.config.grid.levels = levels;
+            }
+            Field::PositionMode => {
+                self.config.position.mode = text.parse().map_err(|e: Error| e.to_string())?
+            }
+            Field::BaseSize => self.config.position.base_size = parse_number(text)?,
+            Field::Multiplier => {
+                self.config.position.multiplier = if text.is_empty() || text == "-" {
+                    None
+                } else {
+                    Some(parse_number(text)?)
+                };
+            }
+        }
+        self.modified = true;
+        Ok(())
+    }
+
+    fn save(&mut self) {
+        if let Err(e) = self.config.validate() {
+            self.status = format!("Not saved: {}", e);
+            return;
+        }
+        match self.config.to_file(&self.path) {
+            Ok(()) => {
+                self.modified = false;
+                self.status = format!("Saved to {}", self.path.display());
+            }
+            Err(e) => self.status = format!("Error saving config: {}", e),
+        }
+    }
+
+    fn render_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        lines.push(format!(
+            "=== Grid Strategy Editor === {}{}",
+            self.path.display(),
+            if self.modified { " [modified]" } else { "" }
+        ));
+        lines.push(String::new());
+
+        for (i, field) in Field::ALL.iter().enumerate() {
+            let marker = if i == self.selected { ">" } else { " " };
+            let value = match &self.input {
+                Some(input) if i == self.selected => format!("{}_", input),
+                _ => self.value(*field),
+            };
+            lines.push(format!("{} {:<15} {}", marker, field.label(), value));
+        }
+
+        lines.push(String::new());
+        lines.push(table::rule());
+        lines.push(table::ladder_header());
+        lines.push(table::rule());
+
+        match self.config.validate() {
+            Ok(()) => {
+                let mut calculator = self.config.calculator();
+                for (i, &level) in self.config.grid.levels.iter().enumerate() {
+                    let result = calculator.calculate_grid(level);
+                    lines.push(table::ladder_row(i + 1, level, &result));
+                }
+                lines.push(table::rule());
+                if let Some(last) = calculator.get_history().last() {
+                    let drop = (1.0 - last.grid_price / self.config.base.initial_price) * 100.0;
+                    lines.push(format!(
+                        "Total Position: {:.2}   Total Cost: {:.2}   \
+                         Avg Price: {:.2}   Max Drop: {:.2}%",
+                        last.total_position, last.total_cost, last.average_price, drop
+                    ));
+                }
+            }
+            Err(e) => {
+                lines.push(format!("Invalid configuration: {}", e));
+                lines.push(table::rule());
+            }
+        }
+
+        for finding in lint::lint(&self.config) {
+            lines.push(finding.to_string());
+        }
+
+        lines.push(String::new());
+        lines.push(self.status.clone());
+        lines.push("↑/↓ select  ←/→ adjust  Enter edit  s save  q quit".to_string());
+        lines
+    }
+}
+
+fn step_for(value: f64) -> f64 {
+    let step = (value.abs() * 0.01).max(0.01);
+    round_cents(step)
+}
+
+fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, direction: f64) -> T {
+    let len = options.len();
+    let index = options.iter().position(|o| *o == current).unwrap_or(0);
+    let next = if direction < 0.0 {
+        (index + len - 1) % len
+    } else {
+        (index + 1) % len
+    };
+    options[next]
+}
+
+fn draw(out: &mut impl Write, app: &App) -> io::Result<()> {
+    queue!(out, cursor::MoveTo(0, 0), terminal::Clear(ClearType::All))?;
+    for (row, line) in app.render_lines().iter().enumerate() {
+        queue!(out, cursor::MoveTo(0, row as u16), style::Print(line))?;
+    }
+    out.flush()
+}
+
+/// Run the interactive editor on `config` until the user quits
+pub fn run(config: Config, path: PathBuf) -> io::Result<()> {
+    let mut app = App::new(config, path);
+    let mut out = io::stdout();
+
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let result = (|| -> io::Result<()> {
+        loop {
+            draw(&mut out, &app)?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && app.handle_key(key) == Action::Quit {
+                    return Ok(());
+                }
+            }
+        }
+    })();
+
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let config: Config = toml::from_str(include_str!("../simple_config.toml")).unwrap();
+        App::new(config, PathBuf::from("test.toml"))
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Action {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_adjust_fields() {
+        let mut app = app();
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.config.base.initial_price, 101.0);
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.config.base.grid_type, GridType::Average);
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Left);
+        assert_eq!(app.config.position.mode, PositionMode::IncrementMultiple);
+        assert!(app.modified);
+    }
+
+    #[test]
+    fn test_edit_levels() {
+        let mut app = app();
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input.as_deref(), Some("1, 2, 3, 5, 8"));
+        while app.input.as_deref() != Some("") {
+            press(&mut app, KeyCode::Backspace);
+        }
+        for c in "1, 5,10".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.config.grid.levels, vec![1.0, 5.0, 10.0]);
+        assert!(app.input.is_none());
+    }
+
+    #[test]
+    fn test_render_recomputes_and_reports_errors() {
+        let mut app = app();
+        assert!(app.render_lines().iter().any(|l| l.starts_with("3 ")));
+
+        app.set_value(Field::PositionMode, "current-multiple")
+            .unwrap();
+        assert!(app
+            .render_lines()
+            .iter()
+            .any(|l| l.starts_with("Invalid configuration")));
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Action::Quit);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::feed::PriceTick;
+use crate::state::{GridState, LadderLevel, Pnl};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Follows market prices against a grid: which unfilled levels the market
+/// has crossed, how far away the next level is, and the PnL of what filled.
+/// The grid state itself is not changed; fills are recorded separately.
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    state: GridState,
+    /// Lowest price seen since watching started
+    low: Option<f64>,
+}
+
+/// What a single price means for the grid
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WatchUpdate {
+    pub timestamp: DateTime<Utc>,
+    pub price: f64,
+    /// Unfilled levels the market has reached since watching started
+    pub crossed: Vec<LadderLevel>,
+    /// Levels crossed for the first time by this price
+    pub newly_crossed: Vec<usize>,
+    /// Highest unfilled level below the price
+    pub next: Option<NextLevel>,
+    pub pnl: Pnl,
+}
+
+/// The level the market reaches next if it keeps falling
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NextLevel {
+    pub level: usize,
+    pub price: f64,
+    /// How far the price has to fall to reach the level, in percent
+    pub distance_percent: f64,
+}
+
+impl Watcher {
+    pub fn new(state: GridState) -> Self {
+        Self { state, low: None }
+    }
+
+    pub fn state(&self) -> &GridState {
+        &self.state
+    }
+
+    /// Take in the next market price
+    pub fn update(&mut self, tick: &PriceTick) -> WatchUpdate {
+        let previous_low = self.low.unwrap_or(f64::INFINITY);
+        let low = previous_low.min(tick.price);
+        self.low = Some(low);
+
+        let remaining = self.state.remaining();
+        let newly_crossed = remaining
+            .iter()
+            .filter(|l| l.result.grid_price >= low && l.result.grid_price < previous_low)
+            .map(|l| l.level)
+            .collect();
+        let next = remaining
+            .iter()
+            .filter(|l| l.result.grid_price < tick.price)
+            .max_by(|a, b| a.result.grid_price.total_cmp(&b.result.grid_price))
+            .map(|l| NextLevel {
+                level: l.level,
+                price: l.result.grid_price,
+                distance_percent: (tick.price - l.result.grid_price) / tick.price * 100.0,
+            });
+
+        WatchUpdate {
+            timestamp: tick.timestamp,
+            price: tick.price,
+            crossed: remaining
+                .into_iter()
+                .filter(|l| l.result.grid_price >= low)
+                .collect(),
+            newly_crossed,
+            next,
+            pnl: self.state.pnl(tick.price),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::PositionMode;
+
+    fn watcher() -> Watcher {
+        let config = Config::test(&[1.0, 2.0, 4.0], PositionMode::Fixed, 10.0, None);
+        let mut state = GridState::new(config, None).unwrap();
+        state.fill(1).unwrap();
+        Watcher::new(state)
+    }
+
+    fn tick(price: f64) -> PriceTick {
+        PriceTick {
+            timestamp: "2024-05-01T00:00:00Z".parse().unwrap(),
+            price,
+        }
+    }
+
+    #[test]
+    fn test_crossed_and_next_level() {
+        let mut watcher = watcher();
+
+        let update = watcher.update(&tick(100.0));
+        assert!(update.crossed.is_empty());
+        let next = update.next.unwrap();
+        assert_eq!((next.level, next.price), (2, 98.0));
+        assert!((next.distance_percent - 2.0).abs() < 1e-9);
+        // Level 1 filled at 99
+        assert_eq!(update.pnl.position, 10.0);
+        assert!((update.pnl.unrealized - 10.0).abs() < 1e-9);
+
+        let update = watcher.update(&tick(97.5));
+        assert_eq!(update.newly_crossed, [2]);
+        assert_eq!(update.next.as_ref().unwrap().level, 3);
+
+        // A bounce keeps level 2 crossed without reporting it again
+        let update = watcher.update(&tick(99.5));
+        assert_eq!(update.crossed.len(), 1);
+        assert!(update.newly_crossed.is_empty());
+        assert_eq!(update.next.unwrap().level, 2);
+
+        let update = watcher.update(&tick(90.0));
+        assert_eq!(update.newly_crossed, [3]);
+        assert!(update.next.is_none());
+        assert!((update.pnl.unrealized + 90.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,308 @@
+use grid_calculator::config::{self, lint, BaseConfig, Config, GridConfig, PositionConfig};
+use grid_calculator::{GridType, PositionMode};
+use std::io::{self, BufRead, Write};
+
+use crate::parse_levels;
+use crate::table;
+
+const DEFAULT_PRICE: f64 = 100.0;
+const DEFAULT_GRID_TYPE: GridType = GridType::Fixed;
+const DEFAULT_LEVELS: &str = "1, 2, 3, 5, 8";
+const DEFAULT_MODE: PositionMode = PositionMode::Fixed;
+const DEFAULT_SIZE: f64 = 100.0;
+const DEFAULT_MULTIPLIER: f64 = 2.0;
+
+/// Values supplied on the command line; they become the defaults offered
+/// by the interactive prompts, or the whole config in non-interactive mode
+#[derive(Debug, Default, Clone)]
+pub struct Answers {
+    pub price: Option<f64>,
+    pub grid_type: Option<GridType>,
+    pub levels: Option<Vec<f64>>,
+    pub mode: Option<PositionMode>,
+    pub size: Option<f64>,
+    pub multiplier: Option<f64>,
+}
+
+impl Answers {
+    /// Build a config without prompting, filling gaps with the defaults
+    pub fn into_config(self) -> Result<Config, String> {
+        let mode = self.mode.unwrap_or(DEFAULT_MODE);
+        let multiplier = match self.multiplier {
+            Some(mult) => Some(mult),
+            None if mode.uses_multiplier() => Some(DEFAULT_MULTIPLIER),
+            None => None,
+        };
+        let levels = match self.levels {
+            Some(levels) => levels,
+            None => parse_levels(DEFAULT_LEVELS)?,
+        };
+
+        let config = Config {
+            base: BaseConfig {
+                initial_price: self.price.unwrap_or(DEFAULT_PRICE),
+                grid_type: self.grid_type.unwrap_or(DEFAULT_GRID_TYPE),
+            },
+            grid: GridConfig { levels },
+            position: PositionConfig {
+                mode,
+                base_size: self.size.unwrap_or(DEFAULT_SIZE),
+                multiplier,
+            },
+            version: config::migrate::CURRENT_VERSION,
+            include: None,
+            strategies: None,
+            lints: None,
+            alerts: None,
+            portfolio: None,
+        };
+        config.validate().map_err(|e| e.to_string())?;
+        Ok(config)
+    }
+}
+
+/// Ask `question` until the answer passes `parse`. An empty answer picks
+/// `default`. Returns `None` if the input ends before a valid answer.
+fn ask<T>(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    default: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> io::Result<Option<T>> {
+    loop {
+        write!(output, "{} [{}]: ", question, default)?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let answer = match line.trim() {
+            "" => default,
+            answer => answer,
+        };
+
+        match parse(answer) {
+            Ok(value) => return Ok(Some(value)),
+            Err(e) => writeln!(output, "  {}", e)?,
+        }
+    }
+}
+
+fn parse_number(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid number: '{}'", value))
+}
+
+/// Walk through every config section, validating each answer as it is
+/// given. Returns `None` if the user aborts or the input ends.
+pub fn run_interactive(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    defaults: Answers,
+) -> io::Result<Option<Config>> {
+    writeln!(output, "\n=== New Grid Strategy ===")?;
+    writeln!(output, "Press Enter to accept the value in brackets.\n")?;
+
+    let price_default = defaults.price.unwrap_or(DEFAULT_PRICE).to_string();
+    let Some(initial_price) = ask(input, output, "Initial price", &price_default, parse_number)?
+    else {
+        return Ok(None);
+    };
+
+    let grid_type_default = defaults.grid_type.unwrap_or(DEFAULT_GRID_TYPE).to_string();
+    let Some(grid_type) = ask(
+        input,
+        output,
+        "Grid type (fixed, average)",
+        &grid_type_default,
+        |value| value.parse::<GridType>().map_err(|e| e.to_string()),
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let levels_default = defaults
+        .levels
+        .map(|levels| table::format_levels(&levels))
+        .unwrap_or_else(|| DEFAULT_LEVELS.to_string());
+    let Some(levels) = ask(
+        input,
+        output,
+        "Grid levels in % (comma-separated)",
+        &levels_default,
+        |value| {
+            let levels = parse_levels(value)?;
+            config::validate_levels(&levels, "grid.levels").map_err(|e| e.to_string())?;
+            Ok(levels)
+        },
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let mode_default = defaults.mode.unwrap_or(DEFAULT_MODE).to_string();
+    let Some(mode) = ask(
+        input,
+        output,
+        "Position mode (fixed, current-multiple, increment-multiple)",
+        &mode_default,
+        |value| value.parse::<PositionMode>().map_err(|e| e.to_string()),
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let size_default = defaults.size.unwrap_or(DEFAULT_SIZE).to_string();
+    let Some(base_size) = ask(
+        input,
+        output,
+        "Base position size",
+        &size_default,
+        parse_number,
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let multiplier = if mode.uses_multiplier() {
+        let multiplier_default = defaults
+            .multiplier
+            .unwrap_or(DEFAULT_MULTIPLIER)
+            .to_string();
+        let Some(mult) = ask(input, output, "Multiplier", &multiplier_default, |value| {
+            let mult = parse_number(value)?;
+            config::validate_multiplier(mode, Some(mult)).map_err(|e| e.to_string())?;
+            Ok(mult)
+        })?
+        else {
+            return Ok(None);
+        };
+        Some(mult)
+    } else {
+        None
+    };
+
+    let config = Config {
+        base: BaseConfig {
+            initial_price,
+            grid_type,
+        },
+        grid: GridConfig { levels },
+        position: PositionConfig {
+            mode,
+            base_size,
+            multiplier,
+        },
+        version: config::migrate::CURRENT_VERSION,
+        include: None,
+        strategies: None,
+        lints: None,
+        alerts: None,
+        portfolio: None,
+    };
+
+    if let Err(e) = config.validate() {
+        writeln!(output, "Invalid configuration: {}", e)?;
+        return Ok(None);
+    }
+
+    write_preview(output, &config)?;
+
+    let Some(confirmed) = ask(
+        input,
+        output,
+        "Write this config? (y/n)",
+        "y",
+        |value| match value.to_lowercase().as_str() {
+            "y" | "yes" => Ok(true),
+            "n" | "no" => Ok(false),
+            _ => Err("Please answer 'y' or 'n'".to_string()),
+        },
+    )?
+    else {
+        return Ok(None);
+    };
+
+    Ok(confirmed.then_some(config))
+}
+
+/// Print the ladder the config would produce
+pub fn write_preview(output: &mut impl Write, config: &Config) -> io::Result<()> {
+    let mut calculator = config.calculator();
+
+    writeln!(output, "\n{}", table::rule())?;
+    writeln!(output, "{}", table::ladder_header())?;
+    writeln!(output, "{}", table::rule())?;
+    for (i, &level) in config.grid.levels.iter().enumerate() {
+        let result = calculator.calculate_grid(level);
+        writeln!(output, "{}", table::ladder_row(i + 1, level, &result))?;
+    }
+    writeln!(output, "{}", table::rule())?;
+    for finding in lint::lint(config) {
+        writeln!(output, "{}", finding)?;
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(script: &str, defaults: Answers) -> (Option<Config>, String) {
+        let mut input = script.as_bytes();
+        let mut output = Vec::new();
+        let config = run_interactive(&mut input, &mut output, defaults).unwrap();
+        (config, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_interactive_reprompts_invalid_answers() {
+        let script = "abc\n120\nsideways\naverage\n1,150\n1,2,4\ncurrent-multiple\n\n3\ny\n";
+        let (config, output) = run(script, Answers::default());
+        let config = config.expect("config should be created");
+
+        assert_eq!(config.base.initial_price, 120.0);
+        assert_eq!(config.base.grid_type, GridType::Average);
+        assert_eq!(config.grid.levels, vec![1.0, 2.0, 4.0]);
+        assert_eq!(config.position.mode, PositionMode::CurrentMultiple);
+        assert_eq!(config.position.base_size, DEFAULT_SIZE);
+        assert_eq!(config.position.multiplier, Some(3.0));
+
+        assert!(output.contains("Invalid number: 'abc'"));
+        assert!(output.contains("Invalid grid_type: sideways"));
+        assert!(output.contains("Invalid grid level: 150"));
+    }
+
+    #[test]
+    fn test_interactive_defaults_and_decline() {
+        let defaults = Answers {
+            price: Some(50.0),
+            ..Answers::default()
+        };
+        let (config, output) = run("\n\n\n\n\nn\n", defaults);
+        assert!(config.is_none());
+        assert!(output.contains("Initial price [50]"));
+        assert!(output.contains("Avg Price"));
+    }
+
+    #[test]
+    fn test_non_interactive_config() {
+        let answers = Answers {
+            price: Some(10.0),
+            mode: Some(PositionMode::IncrementMultiple),
+            ..Answers::default()
+        };
+        let config = answers.into_config().unwrap();
+        assert_eq!(config.position.multiplier, Some(DEFAULT_MULTIPLIER));
+        assert_eq!(config.grid.levels, vec![1.0, 2.0, 3.0, 5.0, 8.0]);
+
+        let invalid = Answers {
+            levels: Some(vec![]),
+            ..Answers::default()
+        };
+        assert!(invalid.into_config().is_err());
+    }
+}